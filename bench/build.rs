@@ -0,0 +1,6 @@
+extern crate entity_store_code_gen;
+
+fn main() {
+    println!("cargo:rerun-if-changed=spec.toml");
+    entity_store_code_gen::generate(include_str!("spec.toml"), "entity_store.rs").unwrap()
+}
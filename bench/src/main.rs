@@ -0,0 +1,67 @@
+//! Compares committing the changes which populate a level one at a
+//! time with committing them all at once with `commit_batch`.
+
+#[macro_use] extern crate enum_primitive;
+#[macro_use] extern crate serde_derive;
+#[macro_use] extern crate entity_store_helper;
+
+#[allow(dead_code, unused_imports)]
+mod entity_store {
+    include_entity_store!("entity_store.rs");
+}
+
+use std::time::{Duration, Instant};
+use entity_store::{insert, EntityChange, EntityWorld, Size, Coord};
+
+const WIDTH: u32 = 200;
+const HEIGHT: u32 = 200;
+const NUM_RUNS: usize = 10;
+
+fn level_changes(world: &mut EntityWorld) -> Vec<EntityChange> {
+    let mut changes = Vec::new();
+    for y in 0..HEIGHT as i32 {
+        for x in 0..WIDTH as i32 {
+            let coord = Coord::new(x, y);
+            let floor = world.spawn();
+            changes.push(insert::coord(floor, coord));
+            changes.push(insert::tile(floor, 0));
+            changes.push(insert::light(floor, 1));
+            if (x * 7 + y * 13) % 3 == 0 {
+                let wall = world.spawn();
+                changes.push(insert::coord(wall, coord));
+                changes.push(insert::tile(wall, 1));
+                changes.push(insert::solid(wall));
+                changes.push(insert::opaque(wall));
+            }
+        }
+    }
+    changes
+}
+
+fn time<F: FnMut(&mut EntityWorld, Vec<EntityChange>)>(mut f: F) -> (Duration, usize) {
+    let mut best = None;
+    let mut num_changes = 0;
+    for _ in 0..NUM_RUNS {
+        let mut world = EntityWorld::new(Size::new(WIDTH, HEIGHT));
+        let changes = level_changes(&mut world);
+        num_changes = changes.len();
+        let start = Instant::now();
+        f(&mut world, changes);
+        let duration = start.elapsed();
+        best = Some(best.map_or(duration, |best: Duration| best.min(duration)));
+    }
+    (best.unwrap(), num_changes)
+}
+
+fn main() {
+    let (single, num_changes) = time(|world, changes| {
+        for change in changes {
+            world.commit(change);
+        }
+    });
+    let (batch, _) = time(|world, changes| world.commit_batch(changes));
+
+    println!("{} changes, best of {} runs", num_changes, NUM_RUNS);
+    println!("commit:       {:?}", single);
+    println!("commit_batch: {:?}", batch);
+}
@@ -4,6 +4,11 @@ pub enum AggregateType {
     Count,
     Set,
     NeighbourCount,
+    Min,
+    Max,
+    Any,
+    Flags,
+    NeighbourSum,
 }
 
 use self::AggregateType::*;
@@ -13,6 +18,11 @@ pub const ALL: &[AggregateType] = &[
     Count,
     Set,
     NeighbourCount,
+    Min,
+    Max,
+    Any,
+    Flags,
+    NeighbourSum,
 ];
 
 impl AggregateType {
@@ -22,6 +32,11 @@ impl AggregateType {
             "count" => Some(Count),
             "set" => Some(Set),
             "neighbour_count" => Some(NeighbourCount),
+            "min" => Some(Min),
+            "max" => Some(Max),
+            "any" => Some(Any),
+            "flags" => Some(Flags),
+            "neighbour_sum" => Some(NeighbourSum),
             _ => None,
         }
     }
@@ -32,6 +47,11 @@ impl AggregateType {
             Count => "count",
             Set => "set",
             NeighbourCount => "neighbour_count",
+            Min => "min",
+            Max => "max",
+            Any => "any",
+            Flags => "flags",
+            NeighbourSum => "neighbour_sum",
         }
     }
 
@@ -41,6 +61,26 @@ impl AggregateType {
             Total => true,
             // we'll need to check if this component is present
             NeighbourCount => true,
+            // we'll need to look up the value to remove it from the multiset
+            Min | Max => true,
+            // we'll need to look up the value to subtract it from neighbours
+            NeighbourSum => true,
+            _ => false,
+        }
+    }
+
+    pub fn is_neighbour(self) -> bool {
+        match self {
+            // these are stored in the cells around the entity
+            NeighbourCount | NeighbourSum => true,
+            _ => false,
+        }
+    }
+
+    pub fn requires_type(self) -> bool {
+        match self {
+            // these aggregate values of the component's type
+            Total | Min | Max | NeighbourSum => true,
             _ => false,
         }
     }
@@ -52,6 +92,11 @@ impl AggregateType {
             Count => field_type.cloned().unwrap_or_else(|| "usize".to_string()),
             Set => field_type.cloned().unwrap_or_else(|| "::std::collections::HashSet<super::EntityId>".to_string()),
             NeighbourCount => "::entity_store_helper::NeighbourCount".to_string(),
+            Min => format!("::entity_store_helper::Min<{}>", component_type.unwrap()),
+            Max => format!("::entity_store_helper::Max<{}>", component_type.unwrap()),
+            Any => "::entity_store_helper::Any".to_string(),
+            Flags => "::entity_store_helper::Flags".to_string(),
+            NeighbourSum => format!("::entity_store_helper::Neighbours<{}>", component_type.unwrap()),
         }
     }
 
@@ -61,6 +106,12 @@ impl AggregateType {
             Count => Some("contains"),
             Set => Some("contains"),
             NeighbourCount => None,
+            Min => Some("get"),
+            Max => Some("get"),
+            Any => Some("contains"),
+            Flags => Some("contains"),
+            // neighbours are updated separately from the entity's own cell
+            NeighbourSum => None,
         }
     }
 }
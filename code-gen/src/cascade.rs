@@ -0,0 +1,33 @@
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Cascade {
+    Detach,
+    Remove,
+    Ignore,
+}
+
+use self::Cascade::*;
+
+pub const ALL: &[Cascade] = &[
+    Detach,
+    Remove,
+    Ignore,
+];
+
+impl Cascade {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "detach" => Some(Detach),
+            "remove" => Some(Remove),
+            "ignore" => Some(Ignore),
+            _ => None,
+        }
+    }
+
+    pub fn to_str(self) -> &'static str {
+        match self {
+            Detach => "detach",
+            Remove => "remove",
+            Ignore => "ignore",
+        }
+    }
+}
@@ -0,0 +1,43 @@
+#[derive(Clone, Copy, Debug)]
+pub enum IndexType {
+    Hash,
+    BTree,
+}
+
+use self::IndexType::*;
+
+pub const ALL: &[IndexType] = &[
+    Hash,
+    BTree,
+];
+
+impl IndexType {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "hash" => Some(Hash),
+            "btree" => Some(BTree),
+            _ => None,
+        }
+    }
+
+    pub fn to_str(self) -> &'static str {
+        match self {
+            Hash => "hash",
+            BTree => "btree",
+        }
+    }
+
+    pub fn to_map_type(self) -> &'static str {
+        match self {
+            Hash => "HashMap",
+            BTree => "BTreeMap",
+        }
+    }
+
+    pub fn to_set_type(self) -> &'static str {
+        match self {
+            Hash => "EntityHashSet",
+            BTree => "EntityBTreeSet",
+        }
+    }
+}
@@ -4,6 +4,7 @@ use result::GenResult as Result;
 
 fn ret_none<T>() -> Option<T> { None }
 fn ret_64() -> usize { 64 }
+fn ret_false() -> bool { false }
 
 #[derive(Debug, Deserialize)]
 pub struct Spec {
@@ -13,8 +14,25 @@ pub struct Spec {
     pub spatial_hash: BTreeMap<String, SpatialHashField>,
     #[serde(default = "ret_none")]
     pub spatial_hash_key: Option<String>,
+    #[serde(default = "BTreeMap::new")]
+    pub spatial_hashes: BTreeMap<String, SpatialHash>,
     #[serde(default = "ret_64")]
     pub id_width: usize,
+    #[serde(default = "ret_false")]
+    pub generational_ids: bool,
+    #[serde(default = "BTreeMap::new")]
+    pub queries: BTreeMap<String, Query>,
+    #[serde(default = "ret_none")]
+    pub layout: Option<String>,
+    #[serde(default = "BTreeMap::new")]
+    pub resources: BTreeMap<String, Resource>,
+    #[serde(default = "BTreeMap::new")]
+    pub relations: BTreeMap<String, Relation>,
+    // maps each component of a prefab to an expression (as a string) or
+    // an integer, float or boolean literal for its default value, or to
+    // `true` for components without a type
+    #[serde(default = "BTreeMap::new")]
+    pub prefabs: BTreeMap<String, BTreeMap<String, toml::Value>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -25,15 +43,71 @@ pub struct Component {
     pub name_override: Option<String>,
     #[serde(default = "ret_none")]
     pub storage: Option<String>,
+    #[serde(default = "ret_false")]
+    pub partial_eq: bool,
+    #[serde(default = "ret_false")]
+    pub track_changes: bool,
+    #[serde(default = "ret_none")]
+    pub index: Option<String>,
+    #[serde(default = "ret_false")]
+    pub unique: bool,
+    #[serde(default = "ret_none")]
+    pub on_conflict: Option<String>,
+    #[serde(default = "Vec::new")]
+    pub requires: Vec<String>,
+    #[serde(default = "Vec::new")]
+    pub excludes: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Resource {
+    #[serde(rename = "type")]
+    pub typ: String,
+    #[serde(default = "ret_none")]
+    pub name_override: Option<String>,
+    #[serde(default = "ret_false")]
+    pub partial_eq: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Relation {
+    #[serde(default = "ret_none")]
+    pub name_override: Option<String>,
+    #[serde(default = "ret_none")]
+    pub storage: Option<String>,
+    #[serde(default = "ret_none")]
+    pub cascade: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SpatialHash {
+    pub position_component: String,
+    #[serde(default = "BTreeMap::new")]
+    pub fields: BTreeMap<String, SpatialHashField>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct SpatialHashField {
-    pub component: String,
+    #[serde(default = "ret_none")]
+    pub component: Option<String>,
+    // the components packed into a `flags` aggregate
+    #[serde(default = "Vec::new")]
+    pub components: Vec<String>,
     #[serde(default = "ret_none")]
     pub aggregate: Option<String>,
     #[serde(rename = "type", default = "ret_none")]
     pub typ: Option<String>,
+    #[serde(default = "ret_none")]
+    pub neighbourhood: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Query {
+    pub components: Vec<String>,
+    #[serde(default = "Vec::new")]
+    pub optional: Vec<String>,
+    #[serde(default = "Vec::new")]
+    pub exclude: Vec<String>,
 }
 
 impl Spec {
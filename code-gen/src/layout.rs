@@ -0,0 +1,29 @@
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Layout {
+    Component,
+    Archetype,
+}
+
+use self::Layout::*;
+
+pub const ALL: &[Layout] = &[
+    Component,
+    Archetype,
+];
+
+impl Layout {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "component" => Some(Component),
+            "archetype" => Some(Archetype),
+            _ => None,
+        }
+    }
+
+    pub fn to_str(self) -> &'static str {
+        match self {
+            Component => "component",
+            Archetype => "archetype",
+        }
+    }
+}
@@ -8,11 +8,16 @@
 //! A simple example:
 //!
 //! ```
+//! # use std::collections::{HashMap, HashSet};
+//! # mod cgmath { pub struct Vector2<T>(T, T); }
+//! # type EntityId = u64;
+//! # type MyTileType = u8;
 //! struct EntityStore {
 //!     position: HashMap<EntityId, ::cgmath::Vector2<f32>>,
 //!     solid: HashSet<EntityId>,
-//!     tile: HashSet<EntityId, MyTileType>,
+//!     tile: HashMap<EntityId, MyTileType>,
 //! }
+//! # fn main() {}
 //! ```
 //!
 //! Note the `solid` field is a `HashSet` rather than a
@@ -22,8 +27,9 @@
 //! This must be used from a build script. A simple build
 //! script looks like:
 //!
-//! ```
+//! ```no_run
 //! extern crate entity_store_code_gen;
+//! # macro_rules! include_str { ($path:expr) => { "" } }
 //!
 //! fn main() {
 //!     entity_store_code_gen::generate(include_str!("spec.toml"), "entity_store.rs").unwrap()
@@ -40,6 +46,11 @@ extern crate tera;
 
 mod storage_type;
 mod aggregate_type;
+mod layout;
+mod cascade;
+mod index_type;
+mod unique_conflict;
+mod neighbourhood;
 mod spec;
 mod result;
 mod input;
@@ -59,20 +70,27 @@ pub struct GeneratedCode {
     text: String,
 }
 
-fn combine_modules(m: &Vec<(String, String)>) -> String {
-    let module_text = m.iter().map(|&(ref name, ref contents)| {
+// Lints allowed in each generated module. Component values are cloned
+// regardless of whether their type is `Copy`, signatures spell out the
+// user's component types in full, and modules may share the name of the
+// module which includes the generated code.
+const MODULE_ALLOW: &str = "    #![allow(clippy::clone_on_copy, clippy::type_complexity, \
+clippy::match_ref_pats, clippy::needless_borrowed_reference, clippy::module_inception)]\n";
+
+fn combine_modules(m: &[(String, String)]) -> String {
+    let module_text = m.iter().map(|(name, contents)| {
         if name == "mod" {
             contents.clone()
         } else {
             let indented = itertools::join(
                 contents.split("\n").map(|s| {
-                    if s == "" {
+                    if s.is_empty() {
                         "".to_string()
                     } else {
                         format!("    {}", s)
                     }
                 }), "\n");
-            format!("mod {} {{\n{}}}", name, indented)
+            format!("mod {} {{\n{}{}}}", name, MODULE_ALLOW, indented)
         }
     });
     itertools::join(module_text, "\n\n")
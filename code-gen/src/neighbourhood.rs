@@ -0,0 +1,43 @@
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Neighbourhood {
+    All,
+    Cardinal,
+    Ordinal,
+}
+
+use self::Neighbourhood::*;
+
+pub const ALL: &[Neighbourhood] = &[
+    All,
+    Cardinal,
+    Ordinal,
+];
+
+impl Neighbourhood {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "all" => Some(All),
+            "cardinal" => Some(Cardinal),
+            "ordinal" => Some(Ordinal),
+            _ => None,
+        }
+    }
+
+    pub fn to_str(self) -> &'static str {
+        match self {
+            All => "all",
+            Cardinal => "cardinal",
+            Ordinal => "ordinal",
+        }
+    }
+
+    /// Name of the type in the `direction` crate which
+    /// iterates over the directions in the neighbourhood
+    pub fn to_directions(self) -> &'static str {
+        match self {
+            All => "Directions",
+            Cardinal => "DirectionsCardinal",
+            Ordinal => "DirectionsOrdinal",
+        }
+    }
+}
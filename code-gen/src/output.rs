@@ -5,6 +5,17 @@ pub struct StorageInfo {
     #[serde(rename = "type")]
     pub typ: String,
     pub rust_type: String,
+    pub set_type: String,
+    pub len: String,
+    pub ids: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexInfo {
+    #[serde(rename = "type")]
+    pub typ: String,
+    pub map_type: String,
+    pub set_type: String,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -16,6 +27,49 @@ pub struct Component {
     pub index: usize,
     pub key: String,
     pub contains: String,
+    pub partial_eq: bool,
+    pub track_changes: bool,
+    pub relation: bool,
+    pub value_index: Option<IndexInfo>,
+    // what happens when a unique component is given to a second entity
+    pub unique: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Resource {
+    #[serde(rename = "type")]
+    pub typ: String,
+    pub name: String,
+    pub key: String,
+    pub partial_eq: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Relation {
+    pub component: Component,
+    pub cascade: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Constraint {
+    #[serde(rename = "type")]
+    pub typ: String,
+    pub component: Component,
+    pub other: Component,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PrefabComponent {
+    pub component: Component,
+    // expression for the default value of a component with a type
+    pub value: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Prefab {
+    pub name: String,
+    pub key: String,
+    pub components: Vec<PrefabComponent>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -37,20 +91,53 @@ pub struct SpatialHashField {
     pub key: String,
     pub aggregate: AggregateInfo,
     pub component: Component,
+    // position of the component in a `flags` aggregate
+    pub bit: usize,
+    // all components of a `flags` aggregate
+    pub flags: Vec<Component>,
+    // type iterating over the directions of a neighbour aggregate
+    pub directions: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
 pub struct SpatialHash {
+    // prefix of the names of the generated table and cell types
+    pub name: String,
+    // name of the table's field in EntityWorld
+    pub key: String,
+    // name of the argument to EntityWorld::new giving the table's size
+    pub size: String,
+    // prefix of the names of the generated iterator types
+    pub iter_prefix: String,
     pub fields: BTreeMap<String, SpatialHashField>,
     pub by_component: BTreeMap<String, ByComponentInfo>,
     pub position_component: Component,
     pub has_neighbours: bool,
 }
 
+#[derive(Debug, Serialize)]
+pub struct Query {
+    pub name: String,
+    pub components: Vec<Component>,
+    pub optional: Vec<Component>,
+    pub exclude: Vec<Component>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct Spec {
     pub components: BTreeMap<String, Component>,
-    pub spatial_hash: Option<SpatialHash>,
+    pub spatial_hashes: Vec<SpatialHash>,
+    pub queries: BTreeMap<String, Query>,
+    pub resources: BTreeMap<String, Resource>,
+    pub relations: BTreeMap<String, Relation>,
+    pub unique_components: Vec<Component>,
+    // whether any unique component is declared with `on_conflict = "error"`,
+    // in which case committing a change can fail
+    pub unique_errors: bool,
+    pub constraints: Vec<Constraint>,
+    pub prefabs: BTreeMap<String, Prefab>,
     pub id_type: String,
+    pub generational_ids: bool,
+    pub layout: String,
     pub num_component_types: usize,
 }
@@ -8,6 +8,17 @@ pub struct Renderer {
     module_names: Vec<&'static str>,
 }
 
+// templates included by other templates rather than rendered as modules
+fn partials() -> Vec<(&'static str, &'static str)> {
+    vec![
+        ("entity_store_tracking",   include_str!("templates/entity_store_tracking.template.rs")),
+        ("entity_store_resources",  include_str!("templates/entity_store_resources.template.rs")),
+        ("entity_store_relations",  include_str!("templates/entity_store_relations.template.rs")),
+        ("entity_store_indexes",    include_str!("templates/entity_store_indexes.template.rs")),
+        ("entity_store_unique",     include_str!("templates/entity_store_unique.template.rs")),
+    ]
+}
+
 impl Renderer {
     pub fn new() -> Result<Self> {
         let mut tera = Tera::default();
@@ -17,17 +28,29 @@ impl Renderer {
             ("id",                      include_str!("templates/id.template.rs")),
             ("component",               include_str!("templates/component.template.rs")),
             ("entity_vec",              include_str!("templates/entity_vec.template.rs")),
+            ("entity_sparse_set",       include_str!("templates/entity_sparse_set.template.rs")),
             ("entity_store",            include_str!("templates/entity_store.template.rs")),
+            ("archetype",               include_str!("templates/archetype.template.rs")),
             ("entity_change",           include_str!("templates/entity_change.template.rs")),
+            ("resource",                include_str!("templates/resource.template.rs")),
             ("component_type_set",      include_str!("templates/component_type_set.template.rs")),
             ("constants",               include_str!("templates/constants.template.rs")),
             ("entity_component_table",  include_str!("templates/entity_component_table.template.rs")),
             ("spatial_hash",            include_str!("templates/spatial_hash.template.rs")),
+            ("modify",                  include_str!("templates/modify.template.rs")),
+            ("query",                   include_str!("templates/query.template.rs")),
             ("entity_id_allocator",     include_str!("templates/entity_id_allocator.template.rs")),
+            ("entity_world",            include_str!("templates/entity_world.template.rs")),
+            ("commit_error",            include_str!("templates/commit_error.template.rs")),
+            ("entity_builder",          include_str!("templates/entity_builder.template.rs")),
+            ("prefab",                  include_str!("templates/prefab.template.rs")),
+            ("change_journal",          include_str!("templates/change_journal.template.rs")),
+            ("entity_hooks",            include_str!("templates/entity_hooks.template.rs")),
         ];
 
         let module_names = templates.iter().map(|&(n, _)| n).collect();
 
+        tera.add_raw_templates(partials())?;
         tera.add_raw_templates(templates)?;
 
         Ok(Renderer {
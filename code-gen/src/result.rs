@@ -20,6 +20,22 @@ pub enum GenError {
     RustFmtError,
     MissingStorageType(String),
     NoComponents,
+    EmptyQuery(String),
+    InvalidLayout(Vec<String>),
+    InvalidCascade(Vec<String>),
+    DuplicateComponent(String),
+    InvalidIndexType(Vec<String>),
+    IndexWithoutType(String),
+    InvalidUniqueConflict(Vec<String>),
+    ConflictWithoutUnique(String),
+    InvalidPrefabValue(String, String),
+    AggregateWithoutType(String),
+    SpatialHashFieldWithoutComponent(String),
+    ComponentsWithoutFlags(String),
+    TooManyFlags(String),
+    InvalidNeighbourhood(Vec<String>),
+    NeighbourhoodWithoutNeighbours(String),
+    DuplicateSpatialHashName(String),
 }
 
 pub type GenResult<T> = result::Result<T, GenError>;
@@ -1,16 +1,50 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use itertools;
 use storage_type::{self, StorageType};
 use aggregate_type::{self, AggregateType};
+use layout::{self, Layout};
+use cascade::{self, Cascade};
+use index_type::{self, IndexType};
+use unique_conflict::{self, UniqueConflict};
+use neighbourhood::{self, Neighbourhood};
 use result::GenResult as Result;
 use result::GenError as Error;
 use input;
+use toml;
 use output;
 
+// flags are packed into a u64
+const MAX_FLAGS: usize = 64;
+
 #[derive(Debug, Clone)]
 pub struct Spec {
     components: ComponentSpec,
     spatial_hash: Option<SpatialHashSpec>,
+    spatial_hashes: BTreeMap<String, SpatialHashSpec>,
+    queries: BTreeMap<String, Query>,
+    layout: Layout,
+    resources: BTreeMap<String, Resource>,
+    relations: BTreeMap<String, Relation>,
+    prefabs: BTreeMap<String, Prefab>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Prefab {
+    name: String,
+    // maps component keys to expressions for their default values
+    components: BTreeMap<String, Option<String>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Relation {
+    cascade: Cascade,
+}
+
+#[derive(Debug, Clone)]
+pub struct Resource {
+    typ: String,
+    name: String,
+    partial_eq: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -19,11 +53,25 @@ pub struct Component {
     name: String,
     key: String,
     storage_type: Option<StorageType>,
+    // with the archetype layout, all components are stored
+    // regardless of their storage type
+    stored: bool,
+    partial_eq: bool,
+    track_changes: bool,
+    // the component holds the id of a related entity
+    relation: bool,
+    index_type: Option<IndexType>,
+    unique: Option<UniqueConflict>,
+    // keys of components which must be present alongside this one
+    requires: Vec<String>,
+    // keys of components which must not be present alongside this one
+    excludes: Vec<String>,
 }
 
 #[derive(Debug, Clone)]
 pub struct ComponentSpec {
     id_width: usize,
+    generational_ids: bool,
     components: BTreeMap<String, Component>,
 }
 
@@ -31,7 +79,10 @@ pub struct ComponentSpec {
 pub struct SpatialHashField {
     typ: Option<String>,
     aggregate_type: Option<AggregateType>,
-    component: Component,
+    // a single component, unless this is a `flags` aggregate
+    components: Vec<Component>,
+    // only present for neighbour aggregates
+    neighbourhood: Option<Neighbourhood>,
 }
 
 #[derive(Debug, Clone)]
@@ -40,6 +91,14 @@ pub struct SpatialHashSpec {
     fields: BTreeMap<String, SpatialHashField>,
 }
 
+#[derive(Debug, Clone)]
+pub struct Query {
+    name: String,
+    components: Vec<String>,
+    optional: Vec<String>,
+    exclude: Vec<String>,
+}
+
 fn capitalise_first_letter(s: &str) -> String {
     let mut chars = s.chars();
     match chars.next() {
@@ -62,8 +121,28 @@ fn valid_aggregate_type_strings() -> Vec<String> {
     aggregate_type::ALL.iter().map(|s| s.to_str().to_string()).collect()
 }
 
+fn valid_layout_strings() -> Vec<String> {
+    layout::ALL.iter().map(|s| s.to_str().to_string()).collect()
+}
+
+fn valid_index_type_strings() -> Vec<String> {
+    index_type::ALL.iter().map(|s| s.to_str().to_string()).collect()
+}
+
+fn valid_unique_conflict_strings() -> Vec<String> {
+    unique_conflict::ALL.iter().map(|s| s.to_str().to_string()).collect()
+}
+
+fn valid_neighbourhood_strings() -> Vec<String> {
+    neighbourhood::ALL.iter().map(|s| s.to_str().to_string()).collect()
+}
+
+fn valid_cascade_strings() -> Vec<String> {
+    cascade::ALL.iter().map(|s| s.to_str().to_string()).collect()
+}
+
 impl Component {
-    fn from_input(field_name: &str, c: &input::Component) -> Result<Self> {
+    fn from_input(field_name: &str, c: &input::Component, layout: Layout) -> Result<Self> {
         let storage_type = if let Some(s) = c.storage.as_ref() {
             if let Some(s) = StorageType::from_str(s.as_str()) {
                 Some(s)
@@ -75,31 +154,100 @@ impl Component {
             None
         };
 
+        let stored = storage_type.is_some() || layout == Layout::Archetype;
+
+        if c.track_changes && !stored {
+            return Err(Error::MissingStorageType(field_name.to_string()));
+        }
+
+        let index_type = if let Some(i) = c.index.as_ref() {
+            if let Some(i) = IndexType::from_str(i.as_str()) {
+                Some(i)
+            } else {
+                return Err(Error::InvalidIndexType(valid_index_type_strings()));
+            }
+        } else {
+            None
+        };
+
+        if index_type.is_some() {
+            if c.typ.is_none() {
+                return Err(Error::IndexWithoutType(field_name.to_string()));
+            }
+            if !stored {
+                return Err(Error::MissingStorageType(field_name.to_string()));
+            }
+        }
+
+        let on_conflict = if let Some(u) = c.on_conflict.as_ref() {
+            if !c.unique {
+                return Err(Error::ConflictWithoutUnique(field_name.to_string()));
+            }
+            if let Some(u) = UniqueConflict::from_str(u.as_str()) {
+                u
+            } else {
+                return Err(Error::InvalidUniqueConflict(valid_unique_conflict_strings()));
+            }
+        } else {
+            UniqueConflict::Replace
+        };
+
+        let unique = if c.unique {
+            if !stored {
+                return Err(Error::MissingStorageType(field_name.to_string()));
+            }
+            Some(on_conflict)
+        } else {
+            None
+        };
+
         let name = c.name_override.as_ref().cloned().unwrap_or_else(|| {
             name_from_field_name(field_name)
         });
 
         Ok(Self {
             storage_type,
+            stored,
             name,
             key: field_name.to_string(),
             typ: c.typ.clone(),
+            partial_eq: c.partial_eq,
+            track_changes: c.track_changes,
+            relation: false,
+            index_type,
+            unique,
+            requires: c.requires.clone(),
+            excludes: c.excludes.clone(),
         })
     }
 
-    fn to_output(&self, key: &str, index: usize) -> output::Component {
-        let storage = self.storage_type.as_ref().map(|s| {
-            output::StorageInfo {
-                typ: s.to_str().to_string(),
-                rust_type: {
-                    if self.typ.is_some() {
-                        s.to_map_type().to_string()
-                    } else {
-                        s.to_set_type().to_string()
-                    }
-                }.to_string(),
-            }
-        });
+    fn to_output(&self, key: &str, index: usize, layout: Layout) -> output::Component {
+        let storage = if layout == Layout::Archetype {
+            // components are stored in the columns of archetype tables
+            Some(output::StorageInfo {
+                typ: layout.to_str().to_string(),
+                rust_type: "Vec".to_string(),
+                set_type: "EntityVecSet".to_string(),
+                len: "len".to_string(),
+                ids: "iter()".to_string(),
+            })
+        } else {
+            self.storage_type.as_ref().map(|s| {
+                output::StorageInfo {
+                    typ: s.to_str().to_string(),
+                    rust_type: {
+                        if self.typ.is_some() {
+                            s.to_map_type().to_string()
+                        } else {
+                            s.to_set_type().to_string()
+                        }
+                    }.to_string(),
+                    set_type: s.to_set_type().to_string(),
+                    len: s.to_len().to_string(),
+                    ids: s.to_ids(self.typ.is_some()).to_string(),
+                }
+            })
+        };
         output::Component {
             typ: self.typ.clone(),
             name: self.name.clone(),
@@ -107,12 +255,127 @@ impl Component {
             key: key.to_string(),
             index,
             contains: if self.typ.is_some() { "contains_key".to_string() } else { "contains".to_string() },
+            partial_eq: self.partial_eq,
+            track_changes: self.track_changes,
+            relation: self.relation,
+            value_index: self.index_type.map(|i| {
+                output::IndexInfo {
+                    typ: i.to_str().to_string(),
+                    map_type: i.to_map_type().to_string(),
+                    set_type: i.to_set_type().to_string(),
+                }
+            }),
+            unique: self.unique.map(|u| u.to_str().to_string()),
+        }
+    }
+}
+
+impl Relation {
+    fn from_input(field_name: &str, r: &input::Relation, layout: Layout) -> Result<(Self, Component)> {
+        let cascade = if let Some(c) = r.cascade.as_ref() {
+            if let Some(c) = Cascade::from_str(c.as_str()) {
+                c
+            } else {
+                return Err(Error::InvalidCascade(valid_cascade_strings()));
+            }
+        } else {
+            Cascade::Detach
+        };
+
+        // a relation is stored as a component containing the id of the related entity
+        let component_in = input::Component {
+            typ: Some("EntityId".to_string()),
+            name_override: r.name_override.clone(),
+            storage: Some(r.storage.clone().unwrap_or_else(|| "hash".to_string())),
+            partial_eq: true,
+            track_changes: false,
+            index: None,
+            unique: false,
+            on_conflict: None,
+            requires: Vec::new(),
+            excludes: Vec::new(),
+        };
+        let mut component = Component::from_input(field_name, &component_in, layout)?;
+        component.relation = true;
+
+        Ok((Self { cascade }, component))
+    }
+
+    fn to_output(&self, key: &str, components: &BTreeMap<String, output::Component>) -> output::Relation {
+        output::Relation {
+            component: components.get(key).unwrap().clone(),
+            cascade: self.cascade.to_str().to_string(),
+        }
+    }
+}
+
+impl Resource {
+    fn from_input(field_name: &str, r: &input::Resource) -> Self {
+        let name = r.name_override.as_ref().cloned().unwrap_or_else(|| {
+            name_from_field_name(field_name)
+        });
+
+        Self {
+            name,
+            typ: r.typ.clone(),
+            partial_eq: r.partial_eq,
+        }
+    }
+
+    fn to_output(&self, key: &str) -> output::Resource {
+        output::Resource {
+            typ: self.typ.clone(),
+            name: self.name.clone(),
+            key: key.to_string(),
+            partial_eq: self.partial_eq,
+        }
+    }
+}
+
+impl Prefab {
+    fn from_input(key: &str, p: &BTreeMap<String, toml::Value>,
+                  components: &BTreeMap<String, Component>) -> Result<Self> {
+        let mut prefab_components = BTreeMap::new();
+        for (k, v) in p.iter() {
+            let component = if let Some(c) = components.get(k) {
+                c
+            } else {
+                return Err(Error::NoSuchComponent(k.clone()));
+            };
+            // strings are rust expressions, other values become literals
+            let value = match (component.typ.as_ref(), v) {
+                (Some(_), toml::Value::String(expr)) => Some(expr.clone()),
+                (Some(_), toml::Value::Integer(i)) => Some(i.to_string()),
+                (Some(_), toml::Value::Float(f)) if f.is_finite() => Some(format!("{:?}", f)),
+                (Some(_), toml::Value::Boolean(b)) => Some(b.to_string()),
+                (None, toml::Value::Boolean(true)) => None,
+                _ => return Err(Error::InvalidPrefabValue(key.to_string(), k.clone())),
+            };
+            prefab_components.insert(k.clone(), value);
+        }
+
+        Ok(Self {
+            name: name_from_field_name(key),
+            components: prefab_components,
+        })
+    }
+
+    fn to_output(&self, key: &str, components: &BTreeMap<String, output::Component>) -> output::Prefab {
+        output::Prefab {
+            name: self.name.clone(),
+            key: key.to_string(),
+            components: self.components.iter().map(|(k, v)| {
+                output::PrefabComponent {
+                    component: components.get(k).unwrap().clone(),
+                    value: v.clone(),
+                }
+            }).collect(),
         }
     }
 }
 
 impl SpatialHashField {
-    fn from_input(f: &input::SpatialHashField,
+    fn from_input(key: &str, f: &input::SpatialHashField,
                   components: &BTreeMap<String, Component>) -> Result<Self> {
         let aggregate_type = if let Some(a) = f.aggregate.as_ref() {
             if let Some(a) = AggregateType::from_str(a.as_str()) {
@@ -125,30 +388,82 @@ impl SpatialHashField {
             None
         };
 
-        let component = if let Some(c) = components.get(&f.component) {
-            c.clone()
+        let is_neighbour = aggregate_type.map(|a| a.is_neighbour()).unwrap_or(false);
+        let neighbourhood = if let Some(n) = f.neighbourhood.as_ref() {
+            if !is_neighbour {
+                return Err(Error::NeighbourhoodWithoutNeighbours(key.to_string()));
+            }
+            if let Some(n) = Neighbourhood::from_str(n.as_str()) {
+                Some(n)
+            } else {
+                return Err(Error::InvalidNeighbourhood(valid_neighbourhood_strings()));
+            }
+        } else if is_neighbour {
+            Some(Neighbourhood::All)
         } else {
-            return Err(Error::NoSuchComponent(f.component.clone()));
+            None
+        };
+
+        let keys = match aggregate_type {
+            Some(AggregateType::Flags) => {
+                if f.component.is_some() || f.components.is_empty() {
+                    return Err(Error::SpatialHashFieldWithoutComponent(key.to_string()));
+                }
+                if f.components.len() > MAX_FLAGS {
+                    return Err(Error::TooManyFlags(key.to_string()));
+                }
+                f.components.clone()
+            }
+            _ => {
+                if !f.components.is_empty() {
+                    return Err(Error::ComponentsWithoutFlags(key.to_string()));
+                }
+                if let Some(c) = f.component.as_ref() {
+                    vec![c.clone()]
+                } else {
+                    return Err(Error::SpatialHashFieldWithoutComponent(key.to_string()));
+                }
+            }
         };
 
-        if let Some(a) = aggregate_type {
-            if a.requires_storage() && component.storage_type.is_none() {
-                return Err(Error::MissingStorageType(f.component.clone()));
+        let mut field_components = Vec::new();
+        for k in keys.iter() {
+            let component = if let Some(c) = components.get(k) {
+                c.clone()
+            } else {
+                return Err(Error::NoSuchComponent(k.clone()));
+            };
+
+            if let Some(a) = aggregate_type {
+                if a.requires_storage() && !component.stored {
+                    return Err(Error::MissingStorageType(k.clone()));
+                }
+                if a.requires_type() && component.typ.is_none() {
+                    return Err(Error::AggregateWithoutType(k.clone()));
+                }
+            }
+
+            if field_components.iter().any(|c: &Component| &c.key == k) {
+                return Err(Error::DuplicateComponent(k.clone()));
             }
+            field_components.push(component);
         }
 
         Ok(Self {
             aggregate_type,
-            component,
+            components: field_components,
             typ: f.typ.clone(),
+            neighbourhood,
         })
     }
 
-    fn to_output(&self, key: &str, components: &BTreeMap<String, output::Component>) -> output::SpatialHashField {
+    /// Returns the output field for each component of this field,
+    /// in the order the components are packed in a `flags` aggregate.
+    fn to_output(&self, key: &str, components: &BTreeMap<String, output::Component>) -> Vec<output::SpatialHashField> {
         let aggregate = self.aggregate_type.map(|a| {
             output::AggregateInfo {
                 typ: a.to_str().to_string(),
-                rust_type: a.to_type(self.component.typ.as_ref(), self.typ.as_ref()),
+                rust_type: a.to_type(self.components[0].typ.as_ref(), self.typ.as_ref()),
             }
         }).unwrap_or_else(|| {
             output::AggregateInfo {
@@ -156,11 +471,162 @@ impl SpatialHashField {
                 rust_type: "()".to_string(),
             }
         });
-        let component = components.get(&self.component.key).unwrap().clone();
-        output::SpatialHashField {
-            key: key.to_string(),
-            aggregate,
-            component,
+        let flags: Vec<output::Component> = match self.aggregate_type {
+            Some(AggregateType::Flags) => {
+                self.components.iter().map(|c| components.get(&c.key).unwrap().clone()).collect()
+            }
+            _ => Vec::new(),
+        };
+        self.components.iter().enumerate().map(|(bit, c)| {
+            output::SpatialHashField {
+                key: key.to_string(),
+                aggregate: aggregate.clone(),
+                component: components.get(&c.key).unwrap().clone(),
+                bit,
+                flags: flags.clone(),
+                directions: self.neighbourhood.map(|n| n.to_directions().to_string()),
+            }
+        }).collect()
+    }
+}
+
+impl SpatialHashSpec {
+    fn from_input(position_component: &str,
+                  fields: &BTreeMap<String, input::SpatialHashField>,
+                  components: &BTreeMap<String, Component>) -> Result<Self> {
+        if !components.contains_key(position_component) {
+            return Err(Error::NoSuchComponent(position_component.to_string()));
+        }
+        let fields: Result<BTreeMap<String, SpatialHashField>> =
+            fields.iter().map(|(f, shf_in)| {
+                SpatialHashField::from_input(f.as_str(), shf_in, components).map(|shf| {
+                    (f.clone(), shf)
+                })
+            }).collect();
+        let fields = fields?;
+
+        // each flag gets an accessor on the cell named after its component
+        let mut flags = BTreeSet::new();
+        for f in fields.values() {
+            if let Some(AggregateType::Flags) = f.aggregate_type {
+                for c in f.components.iter() {
+                    if !flags.insert(c.key.clone()) {
+                        return Err(Error::DuplicateComponent(c.key.clone()));
+                    }
+                }
+            }
+        }
+
+        Ok(Self {
+            position_component: position_component.to_string(),
+            fields,
+        })
+    }
+
+    fn to_output(&self, key: Option<&str>, components: &BTreeMap<String, output::Component>) -> output::SpatialHash {
+        let field_outputs: BTreeMap<String, Vec<output::SpatialHashField>> = self.fields.iter()
+            .map(|(k, f)| (k.clone(), f.to_output(k, components))).collect();
+        // fields are declared once, but a `flags` field is
+        // maintained through each of its components
+        let fields = field_outputs.iter()
+            .map(|(k, f)| (k.clone(), f[0].clone())).collect();
+        let position_component = components.get(&self.position_component).cloned().unwrap();
+        let mut has_neighbours = false;
+        let mut by_component = BTreeMap::new();
+        let per_component = izip!(self.fields.values(), field_outputs.values())
+            .flat_map(|(g, fs)| fs.iter().map(move |f| (f, g)));
+        for (f, g) in per_component {
+            let current = by_component.entry(f.component.key.clone())
+                .or_insert_with(|| output::ByComponentInfo {
+                    fields: BTreeMap::new(),
+                    lookup: None,
+                    component: f.component.clone(),
+                });
+
+            if let Some(a) = g.aggregate_type {
+                if a.is_neighbour() {
+                    has_neighbours = true;
+                }
+                if let Some(l) = a.to_lookup() {
+                    current.lookup = match l {
+                        "get" => Some("get"),
+                        "contains" => {
+                            if let Some(ref l) = current.lookup {
+                                if l.as_str() == "get" {
+                                    Some("get")
+                                } else {
+                                    Some("contains")
+                                }
+                            } else {
+                                Some("contains")
+                            }
+                        }
+                        _ => unreachable!(),
+                    }.map(|s| s.to_string());
+                }
+            }
+
+            current.fields.insert(f.key.clone(), f.clone());
+        }
+        // the unnamed spatial hash keeps the names it had before
+        // multiple spatial hashes were supported
+        // iterators of named spatial hashes include "SpatialHash" in their
+        // names so they can't collide with those of other spatial hashes
+        let (name, key, size, iter_prefix) = match key {
+            Some(k) => {
+                let name = name_from_field_name(k);
+                let iter_prefix = format!("{}SpatialHash", name);
+                (name, format!("spatial_hash_{}", k), format!("{}_size", k), iter_prefix)
+            }
+            None => (String::new(), "spatial_hash".to_string(), "size".to_string(), String::new()),
+        };
+        output::SpatialHash {
+            name,
+            key,
+            size,
+            iter_prefix,
+            fields,
+            by_component,
+            position_component,
+            has_neighbours,
+        }
+    }
+}
+
+impl Query {
+    fn from_input(key: &str, q: &input::Query,
+                  components: &BTreeMap<String, Component>) -> Result<Self> {
+        if q.components.is_empty() {
+            return Err(Error::EmptyQuery(key.to_string()));
+        }
+
+        for c in q.components.iter().chain(q.optional.iter()).chain(q.exclude.iter()) {
+            if let Some(component) = components.get(c) {
+                if !component.stored {
+                    return Err(Error::MissingStorageType(c.clone()));
+                }
+            } else {
+                return Err(Error::NoSuchComponent(c.clone()));
+            }
+        }
+
+        Ok(Self {
+            name: name_from_field_name(key),
+            components: q.components.clone(),
+            optional: q.optional.clone(),
+            exclude: q.exclude.clone(),
+        })
+    }
+
+    fn to_output(&self, components: &BTreeMap<String, output::Component>) -> output::Query {
+        let lookup = |keys: &Vec<String>| {
+            keys.iter().map(|k| components.get(k).unwrap().clone()).collect()
+        };
+        output::Query {
+            name: self.name.clone(),
+            components: lookup(&self.components),
+            optional: lookup(&self.optional),
+            exclude: lookup(&self.exclude),
         }
     }
 }
@@ -169,114 +635,169 @@ impl Spec {
     pub fn from_str(s: &str) -> Result<Self> {
         let spec_in = input::Spec::from_str(s)?;
 
+        let layout = if let Some(l) = spec_in.layout.as_ref() {
+            if let Some(l) = Layout::from_str(l.as_str()) {
+                l
+            } else {
+                return Err(Error::InvalidLayout(valid_layout_strings()));
+            }
+        } else {
+            Layout::Component
+        };
+
         let components: Result<BTreeMap<String, Component>> =
             spec_in.components.iter().map(|(f, c_in)| {
-                Component::from_input(f.as_str(), c_in).map(|c| {
+                Component::from_input(f.as_str(), c_in, layout).map(|c| {
                     (f.clone(), c)
                 })
             }).collect();
-        let components = components?;
+        let mut components = components?;
+
+        let mut relations = BTreeMap::new();
+        for (f, r_in) in spec_in.relations.iter() {
+            if components.contains_key(f) {
+                return Err(Error::DuplicateComponent(f.clone()));
+            }
+            let (relation, component) = Relation::from_input(f.as_str(), r_in, layout)?;
+            components.insert(f.clone(), component);
+            relations.insert(f.clone(), relation);
+        }
 
         if components.is_empty() {
             return Err(Error::NoComponents);
         }
 
-        let spatial_hash_fields: Result<BTreeMap<String, SpatialHashField>> =
-            spec_in.spatial_hash.iter().map(|(f, shf_in)| {
-                SpatialHashField::from_input(&shf_in, &components).map(|shf| {
-                    (f.clone(), shf)
-                })
-            }).collect();
-        let spatial_hash_fields = spatial_hash_fields?;
+        for c in components.values() {
+            for other in c.requires.iter().chain(c.excludes.iter()) {
+                if !components.contains_key(other) {
+                    return Err(Error::NoSuchComponent(other.clone()));
+                }
+            }
+        }
 
         let spatial_hash = if let Some(shk) = spec_in.spatial_hash_key.as_ref() {
-            if !components.contains_key(shk) {
-                return Err(Error::NoSuchComponent(shk.clone()));
-            }
-            Some(SpatialHashSpec {
-                position_component: shk.clone(),
-                fields: spatial_hash_fields,
-            })
+            Some(SpatialHashSpec::from_input(shk.as_str(), &spec_in.spatial_hash, &components)?)
         } else {
-            if !spatial_hash_fields.is_empty() {
+            if !spec_in.spatial_hash.is_empty() {
                 return Err(Error::MissingSpatialHashKey);
             }
             None
         };
 
+        let spatial_hashes: Result<BTreeMap<String, SpatialHashSpec>> =
+            spec_in.spatial_hashes.iter().map(|(k, sh_in)| {
+                SpatialHashSpec::from_input(sh_in.position_component.as_str(), &sh_in.fields, &components).map(|sh| {
+                    (k.clone(), sh)
+                })
+            }).collect();
+        let spatial_hashes = spatial_hashes?;
+
+        let mut spatial_hash_names = BTreeSet::new();
+        for k in spatial_hashes.keys() {
+            let name = name_from_field_name(k);
+            // the unnamed spatial hash's types have an empty name
+            if name.is_empty() || !spatial_hash_names.insert(name) {
+                return Err(Error::DuplicateSpatialHashName(k.clone()));
+            }
+        }
+
+        let queries: Result<BTreeMap<String, Query>> =
+            spec_in.queries.iter().map(|(k, q_in)| {
+                Query::from_input(k.as_str(), q_in, &components).map(|q| {
+                    (k.clone(), q)
+                })
+            }).collect();
+        let queries = queries?;
+
+        let prefabs: Result<BTreeMap<String, Prefab>> =
+            spec_in.prefabs.iter().map(|(k, p_in)| {
+                Prefab::from_input(k.as_str(), p_in, &components).map(|p| {
+                    (k.clone(), p)
+                })
+            }).collect();
+        let prefabs = prefabs?;
+
+        let resources = spec_in.resources.iter().map(|(k, r_in)| {
+            (k.clone(), Resource::from_input(k.as_str(), r_in))
+        }).collect();
+
         let valid_id_widths = &[8, 16, 32, 64];
         if !valid_id_widths.contains(&spec_in.id_width) {
-            return Err(Error::InvalidIdWidth(valid_id_widths.iter().cloned().collect()));
+            return Err(Error::InvalidIdWidth(valid_id_widths.to_vec()));
         }
 
         let components = ComponentSpec {
             components,
             id_width: spec_in.id_width,
+            generational_ids: spec_in.generational_ids,
         };
 
         Ok(Self {
             components,
             spatial_hash,
+            spatial_hashes,
+            queries,
+            layout,
+            resources,
+            relations,
+            prefabs,
         })
     }
 
     pub fn to_output(&self) -> output::Spec {
         let components: BTreeMap<String, output::Component> = self.components.components.iter()
             .enumerate()
-            .map(|(i, (k, v))| (k.clone(), v.to_output(k, i)) ).collect();
-
-        let spatial_hash = self.spatial_hash.as_ref().map(|sh| {
-            let fields: BTreeMap<String, output::SpatialHashField> = sh.fields.iter()
-                .map(|(k, f)| (k.clone(), f.to_output(k, &components))).collect();
-            let position_component = components.get(&sh.position_component).cloned().unwrap();
-            let mut has_neighbours = false;
-            let mut by_component = BTreeMap::new();
-            for (f, g) in izip!(fields.values(), sh.fields.values()) {
-                let current = by_component.entry(f.component.key.clone())
-                    .or_insert_with(|| output::ByComponentInfo {
-                        fields: BTreeMap::new(),
-                        lookup: None,
-                        component: f.component.clone(),
-                    });
-
-                if let Some(a) = g.aggregate_type {
-                    if let AggregateType::NeighbourCount = a {
-                        has_neighbours = true;
-                    }
-                    if let Some(l) = a.to_lookup() {
-                        current.lookup = match l {
-                            "get" => Some("get"),
-                            "contains" => {
-                                if let Some(ref l) = current.lookup {
-                                    if l.as_str() == "get" {
-                                        Some("get")
-                                    } else {
-                                        Some("contains")
-                                    }
-                                } else {
-                                    Some("contains")
-                                }
-                            }
-                            _ => unreachable!(),
-                        }.map(|s| s.to_string());
-                    }
-                }
+            .map(|(i, (k, v))| (k.clone(), v.to_output(k, i, self.layout)) ).collect();
 
-                current.fields.insert(f.key.clone(), f.clone());
-            }
-            output::SpatialHash {
-                fields,
-                by_component,
-                position_component,
-                has_neighbours,
+        let spatial_hashes = self.spatial_hash.iter().map(|sh| sh.to_output(None, &components))
+            .chain(self.spatial_hashes.iter().map(|(k, sh)| sh.to_output(Some(k.as_str()), &components)))
+            .collect();
+
+        let queries = self.queries.iter()
+            .map(|(k, q)| (k.clone(), q.to_output(&components))).collect();
+
+        let resources = self.resources.iter()
+            .map(|(k, r)| (k.clone(), r.to_output(k))).collect();
+
+        let relations = self.relations.iter()
+            .map(|(k, r)| (k.clone(), r.to_output(k, &components))).collect();
+
+        let unique_components = components.values()
+            .filter(|c| c.unique.is_some()).cloned().collect::<Vec<_>>();
+        let unique_errors = self.components.components.values()
+            .any(|c| c.unique == Some(UniqueConflict::Error));
+
+        let prefabs = self.prefabs.iter()
+            .map(|(k, p)| (k.clone(), p.to_output(k, &components))).collect();
+
+        let mut constraints = Vec::new();
+        for (k, c) in self.components.components.iter() {
+            let component = components.get(k).unwrap();
+            let typed = c.requires.iter().map(|o| ("requires", o))
+                .chain(c.excludes.iter().map(|o| ("excludes", o)));
+            for (typ, other) in typed {
+                constraints.push(output::Constraint {
+                    typ: typ.to_string(),
+                    component: component.clone(),
+                    other: components.get(other).unwrap().clone(),
+                });
             }
-        });
+        }
 
         output::Spec {
+            unique_components,
+            unique_errors,
+            constraints,
+            prefabs,
+            queries,
+            resources,
+            relations,
             num_component_types: self.components.components.len(),
             components,
             id_type: format!("u{}", self.components.id_width),
-            spatial_hash,
+            generational_ids: self.components.generational_ids,
+            layout: self.layout.to_str().to_string(),
+            spatial_hashes,
         }
     }
 }
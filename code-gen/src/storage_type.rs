@@ -3,6 +3,7 @@ pub enum StorageType {
     Vector,
     Hash,
     BTree,
+    SparseSet,
 }
 
 use self::StorageType::*;
@@ -11,6 +12,7 @@ pub const ALL: &[StorageType] = &[
     Vector,
     Hash,
     BTree,
+    SparseSet,
 ];
 
 impl StorageType {
@@ -19,6 +21,7 @@ impl StorageType {
             "vector" => Some(Vector),
             "hash" => Some(Hash),
             "btree" => Some(BTree),
+            "sparse_set" => Some(SparseSet),
             _ => None,
         }
     }
@@ -28,6 +31,7 @@ impl StorageType {
             Vector => "vector",
             Hash => "hash",
             BTree => "btree",
+            SparseSet => "sparse_set",
         }
     }
 
@@ -36,6 +40,28 @@ impl StorageType {
             Vector => "EntityVecMap",
             Hash => "EntityHashMap",
             BTree => "EntityBTreeMap",
+            SparseSet => "EntitySparseSetMap",
+        }
+    }
+
+    pub fn to_len(self) -> &'static str {
+        match self {
+            // vector storage doesn't track how many entries it contains
+            Vector => "len_upper_bound",
+            Hash => "len",
+            BTree => "len",
+            SparseSet => "len",
+        }
+    }
+
+    /// Expression which iterates over the ids in a storage of this
+    /// type, holding either component values or flags.
+    pub fn to_ids(self, has_values: bool) -> &'static str {
+        match (self, has_values) {
+            (Vector, true) | (SparseSet, true) => "iter().map(|(id, _)| id)",
+            (Vector, false) | (SparseSet, false) => "iter()",
+            (Hash, true) | (BTree, true) => "keys().cloned()",
+            (Hash, false) | (BTree, false) => "iter().cloned()",
         }
     }
 
@@ -44,6 +70,7 @@ impl StorageType {
             Vector => "EntityVecSet",
             Hash => "EntityHashSet",
             BTree => "EntityBTreeSet",
+            SparseSet => "EntitySparseSet",
         }
     }
 }
@@ -0,0 +1,525 @@
+{% if layout == "archetype" %}
+#![allow(dead_code)]
+#![allow(unused_imports)]
+#![allow(unused_variables)]
+use std::mem;
+use std::collections::{HashMap, BTreeMap};
+use std::ops::RangeBounds;
+use super::{EntityId, EntityVecSet, EntityHashMap, EntityHashSet, EntityBTreeSet, EntityChange,
+            {% if unique_components %}UniqueViolation,{% endif %} ComponentValue, ComponentRef, ComponentType, ComponentTypeSet,
+            entity_id_index, entity_id_is_stale, insert, remove};
+use entity_store_helper::append::Append;
+{% if resources %}
+use super::{Resources, ResourceType, ResourceValue, ResourceRef, set, clear};
+{% endif %}
+
+/// A table of entities which all have exactly the same set of components.
+/// Each component with a type is stored in its own column, and an entity
+/// occupies the same row of every column.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Archetype {
+    component_types: ComponentTypeSet,
+    ids: Vec<EntityId>,
+    {% for key, component in components %}
+        {% if component.type %}
+            {{ key }}: Vec<{{ component.type }}>,
+        {% endif %}
+    {% endfor %}
+}
+
+impl Archetype {
+    fn new(component_types: ComponentTypeSet) -> Self {
+        Self {
+            component_types,
+            ids: Vec::new(),
+            {% for key, component in components %}
+                {% if component.type %}
+                    {{ key }}: Vec::new(),
+                {% endif %}
+            {% endfor %}
+        }
+    }
+
+    pub fn component_types(&self) -> ComponentTypeSet {
+        self.component_types
+    }
+
+    pub fn ids(&self) -> &[EntityId] {
+        &self.ids
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    {% for key, component in components %}
+        {% if component.type %}
+    /// The `{{ key }}` column. This is empty if entities in this
+    /// archetype don't have a `{{ key }}` component.
+    pub fn {{ key }}(&self) -> &[{{ component.type }}] {
+        &self.{{ key }}
+    }
+        {% endif %}
+    {% endfor %}
+
+    fn push(&mut self, id: EntityId, values: Vec<ComponentValue>) {
+        self.ids.push(id);
+        for value in values {
+            match value {
+                {% for key, component in components %}
+                    {% if component.type %}
+                        ComponentValue::{{ component.name }}(value) => self.{{ key }}.push(value),
+                    {% else %}
+                        ComponentValue::{{ component.name }} => {}
+                    {% endif %}
+                {% endfor %}
+            }
+        }
+    }
+
+    // Removes a row, moving the last row into its place.
+    fn swap_remove(&mut self, row: usize) -> Vec<ComponentValue> {
+        self.ids.swap_remove(row);
+        let mut values = Vec::new();
+        {% for key, component in components %}
+            if self.component_types.contains(ComponentType::{{ component.name }}) {
+                {% if component.type %}
+                    values.push(ComponentValue::{{ component.name }}(self.{{ key }}.swap_remove(row)));
+                {% else %}
+                    values.push(ComponentValue::{{ component.name }});
+                {% endif %}
+            }
+        {% endfor %}
+        values
+    }
+
+    fn replace(&mut self, row: usize, value: ComponentValue) -> ComponentValue {
+        match value {
+            {% for key, component in components %}
+                {% if component.type %}
+                    ComponentValue::{{ component.name }}(value) => {
+                        ComponentValue::{{ component.name }}(mem::replace(&mut self.{{ key }}[row], value))
+                    }
+                {% else %}
+                    ComponentValue::{{ component.name }} => ComponentValue::{{ component.name }},
+                {% endif %}
+            {% endfor %}
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct EntityLocation {
+    archetype: usize,
+    row: usize,
+}
+
+/// Stores the components of each entity in the archetype table
+/// matching the entity's set of components. Adding or removing a
+/// component moves the entity to a different archetype.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntityStore {
+    archetypes: Vec<Archetype>,
+    // maps each set of component types to its archetype, rebuilt
+    // from `archetypes` when missing after deserialization
+    #[serde(skip)]
+    archetype_indices: HashMap<ComponentTypeSet, usize>,
+    // indexed by entity id index
+    locations: Vec<Option<EntityLocation>>,
+    {% if resources %}
+    resources: Resources,
+    {% endif %}
+    {% for key, relation in relations %}
+    children_{{ key }}: EntityHashMap<Vec<EntityId>>,
+    {% endfor %}
+    {% for key, component in components %}
+        {% if component.value_index %}
+    index_{{ key }}: {{ component.value_index.map_type }}<{{ component.type }}, {{ component.value_index.set_type }}>,
+        {% endif %}
+        {% if component.unique %}
+    owner_{{ key }}: Option<EntityId>,
+        {% endif %}
+    {% endfor %}
+    {% for key, component in components %}
+        {% if component.track_changes %}
+            #[serde(skip)]
+            added_{{ key }}: {{ component.storage.set_type }},
+            #[serde(skip)]
+            changed_{{ key }}: {{ component.storage.set_type }},
+            #[serde(skip)]
+            removed_{{ key }}: {{ component.storage.set_type }},
+        {% endif %}
+    {% endfor %}
+}
+
+impl EntityStore {
+    pub fn new() -> Self {
+        Self {
+            archetypes: Vec::new(),
+            archetype_indices: HashMap::new(),
+            locations: Vec::new(),
+            {% if resources %}
+            resources: Resources::default(),
+            {% endif %}
+            {% for key, relation in relations %}
+            children_{{ key }}: EntityHashMap::default(),
+            {% endfor %}
+            {% for key, component in components %}
+                {% if component.value_index %}
+                    index_{{ key }}: {{ component.value_index.map_type }}::default(),
+                {% endif %}
+                {% if component.unique %}
+                    owner_{{ key }}: None,
+                {% endif %}
+            {% endfor %}
+            {% for key, component in components %}
+                {% if component.track_changes %}
+                    added_{{ key }}: {{ component.storage.set_type }}::default(),
+                    changed_{{ key }}: {{ component.storage.set_type }}::default(),
+                    removed_{{ key }}: {{ component.storage.set_type }}::default(),
+                {% endif %}
+            {% endfor %}
+        }
+    }
+
+    pub fn archetypes(&self) -> &[Archetype] {
+        &self.archetypes
+    }
+
+    pub fn component_types(&self, id: EntityId) -> ComponentTypeSet {
+        self.location(id)
+            .map(|location| self.archetypes[location.archetype].component_types)
+            .unwrap_or_default()
+    }
+
+    fn location(&self, id: EntityId) -> Option<EntityLocation> {
+        self.locations.get(entity_id_index(id)).and_then(|location| *location).and_then(|location| {
+            // the slot may be occupied by a different generation of this id
+            if self.archetypes[location.archetype].ids[location.row] == id {
+                Some(location)
+            } else {
+                None
+            }
+        })
+    }
+
+    fn archetype_index(&mut self, component_types: ComponentTypeSet) -> usize {
+        if self.archetype_indices.len() != self.archetypes.len() {
+            self.archetype_indices = self.archetypes.iter().enumerate()
+                .map(|(index, archetype)| (archetype.component_types, index))
+                .collect();
+        }
+        if let Some(&index) = self.archetype_indices.get(&component_types) {
+            return index;
+        }
+        let index = self.archetypes.len();
+        self.archetypes.push(Archetype::new(component_types));
+        self.archetype_indices.insert(component_types, index);
+        index
+    }
+
+    // Removes an entity from its archetype, returning all its components.
+    fn take(&mut self, id: EntityId) -> Option<(ComponentTypeSet, Vec<ComponentValue>)> {
+        let location = self.location(id)?;
+        self.locations[entity_id_index(id)] = None;
+        let archetype = &mut self.archetypes[location.archetype];
+        let values = archetype.swap_remove(location.row);
+        if let Some(&moved) = archetype.ids.get(location.row) {
+            self.locations[entity_id_index(moved)] = Some(location);
+        }
+        Some((archetype.component_types, values))
+    }
+
+    // Returns the id of the entity stored in the same slot as the given id, if any.
+    fn occupant(&self, id: EntityId) -> Option<EntityId> {
+        self.locations.get(entity_id_index(id)).and_then(|location| *location)
+            .map(|location| self.archetypes[location.archetype].ids[location.row])
+    }
+
+    // Returns true if the slot of the id is occupied by an entity allocated
+    // after the id was freed. Changes to such ids are ignored.
+    fn is_stale(&self, id: EntityId) -> bool {
+        self.occupant(id).map(|occupant| entity_id_is_stale(id, occupant)).unwrap_or(false)
+    }
+
+    // Adds an entity which isn't currently stored to the archetype for its
+    // components. An older entity in the same slot is evicted, but nothing
+    // is added if the slot's occupant is newer than the entity.
+    fn put(&mut self, id: EntityId, component_types: ComponentTypeSet, values: Vec<ComponentValue>) {
+        let index = entity_id_index(id);
+        if let Some(occupant) = self.occupant(id) {
+            if !entity_id_is_stale(occupant, id) {
+                // the id itself has since been freed
+                return;
+            }
+            // the slot belongs to an entity which has since been freed, whose
+            // components are removed so indexes and relations are kept in sync
+            let location = self.locations[index].expect("Occupied slot without location");
+            for component_type in self.archetypes[location.archetype].component_types.iter() {
+                self.remove(occupant, component_type);
+            }
+        }
+        if component_types.is_empty() {
+            return;
+        }
+        let archetype = self.archetype_index(component_types);
+        let row = self.archetypes[archetype].len();
+        self.archetypes[archetype].push(id, values);
+        if index >= self.locations.len() {
+            self.locations.resize(index + 1, None);
+        }
+        self.locations[index] = Some(EntityLocation { archetype, row });
+    }
+
+    {% for key, component in components %}
+        {% if component.type %}
+    pub fn get_{{ key }}(&self, id: EntityId) -> Option<&{{ component.type }}> {
+        self.location(id).and_then(|location| {
+            self.archetypes[location.archetype].{{ key }}.get(location.row)
+        })
+    }
+        {% endif %}
+
+    pub fn contains_{{ key }}(&self, id: EntityId) -> bool {
+        self.component_types(id).contains(ComponentType::{{ component.name }})
+    }
+    {% endfor %}
+
+    fn track_insert(&mut self, id: EntityId, component_type: ComponentType, existed: bool) {
+        {% for key, component in components %}
+            {% if component.track_changes %}
+                if component_type == ComponentType::{{ component.name }} {
+                    self.track_insert_{{ key }}(id, existed);
+                }
+            {% endif %}
+        {% endfor %}
+    }
+
+    fn track_remove(&mut self, id: EntityId, component_type: ComponentType, existed: bool) {
+        {% for key, component in components %}
+            {% if component.track_changes %}
+                if component_type == ComponentType::{{ component.name }} {
+                    self.track_remove_{{ key }}(id, existed);
+                }
+            {% endif %}
+        {% endfor %}
+    }
+
+    /// Applies a change to the store.
+    {%- if unique_errors %}
+    /// Changes which would give a unique component declared with
+    /// `on_conflict = "error"` to a second entity are rejected,
+    /// leaving the store unchanged.
+    {%- endif %}
+    pub fn commit(&mut self, change: EntityChange){% if unique_errors %} -> Result<(), UniqueViolation>{% endif %} {
+        {% if unique_errors %}
+        self.check_unique(&change)?;
+        self.apply(change);
+        Ok(())
+        {% else %}
+        self.apply(change);
+        {% endif %}
+    }
+
+    // Applies a change which is known not to violate a unique component.
+    pub(super) fn apply(&mut self, change: EntityChange) {
+        match change {
+            EntityChange::Insert(id, value) => { self.insert(id, value); }
+            EntityChange::Remove(id, typ) => { self.remove(id, typ); }
+            {% if resources %}
+            EntityChange::SetResource(value) => { self.set_resource(value); }
+            EntityChange::ClearResource(typ) => { self.clear_resource(typ); }
+            {% endif %}
+        }
+    }
+
+    pub fn get(&self, id: EntityId, component_type: ComponentType) -> Option<ComponentRef<'_>> {
+        match component_type {
+            {% for key, component in components %}
+                ComponentType::{{ component.name }} => {
+                    {% if component.type %}
+                        self.get_{{ key }}(id).map(ComponentRef::{{ component.name }})
+                    {% else %}
+                        if self.contains_{{ key }}(id) {
+                            Some(ComponentRef::{{ component.name }})
+                        } else {
+                            None
+                        }
+                    {% endif %}
+                }
+            {% endfor %}
+        }
+    }
+
+    pub fn contains(&self, id: EntityId, component_type: ComponentType) -> bool {
+        self.component_types(id).contains(component_type)
+    }
+
+    pub fn remove(&mut self, id: EntityId, component_type: ComponentType) -> Option<ComponentValue> {
+        if !self.contains(id, component_type) {
+            return None;
+        }
+        let (mut component_types, mut values) = self.take(id)?;
+        component_types.remove(component_type);
+        let index = values.iter().position(|value| value.typ() == component_type)
+            .expect("Component missing from archetype");
+        let previous = values.swap_remove(index);
+        self.put(id, component_types, values);
+        self.track_remove(id, component_type, true);
+        self.update_indexes(id, component_type, Some(&previous));
+        {% if unique_components %}
+        self.update_owner(id, component_type);
+        {% endif %}
+        Some(previous)
+    }
+
+    pub fn insert(&mut self, id: EntityId, component_value: ComponentValue) -> Option<ComponentValue> {
+        let component_type = component_value.typ();
+        if self.is_stale(id) {
+            return None;
+        }
+        {% if unique_components %}
+        if !self.claim(id, component_type) {
+            return None;
+        }
+        {% endif %}
+        let previous = match self.location(id) {
+            Some(location) if self.archetypes[location.archetype].component_types.contains(component_type) => {
+                // the entity stays in the same archetype
+                Some(self.archetypes[location.archetype].replace(location.row, component_value))
+            }
+            _ => {
+                let (mut component_types, mut values) = self.take(id)
+                    .unwrap_or_else(|| (ComponentTypeSet::new(), Vec::new()));
+                component_types.insert(component_type);
+                values.push(component_value);
+                self.put(id, component_types, values);
+                None
+            }
+        };
+        self.track_insert(id, component_type, previous.is_some());
+        self.update_indexes(id, component_type, previous.as_ref());
+        {% if unique_components %}
+        self.update_owner(id, component_type);
+        {% endif %}
+        previous
+    }
+
+    // Keeps relations and value indexes in sync after a component changes.
+    fn update_indexes(&mut self, id: EntityId, component_type: ComponentType, previous: Option<&ComponentValue>) {
+        {% for key, relation in relations %}
+            if component_type == ComponentType::{{ relation.component.name }} {
+                let previous = match previous {
+                    Some(&ComponentValue::{{ relation.component.name }}(parent)) => Some(parent),
+                    _ => None,
+                };
+                self.relate_{{ key }}(id, previous);
+            }
+        {% endfor %}
+        {% for key, component in components %}
+            {% if component.value_index %}
+                if component_type == ComponentType::{{ component.name }} {
+                    let previous = match previous {
+                        Some(&ComponentValue::{{ component.name }}(ref value)) => Some(value),
+                        _ => None,
+                    };
+                    self.reindex_{{ key }}(id, previous);
+                }
+            {% endif %}
+        {% endfor %}
+    }
+
+    {% include "entity_store_tracking" %}
+
+    {% include "entity_store_resources" %}
+
+    {% include "entity_store_relations" %}
+
+    {% include "entity_store_indexes" %}
+
+    {% include "entity_store_unique" %}
+
+    pub fn clone_values<A: Append<(EntityId, ComponentValue)>>(&self, buf: &mut A) {
+        for archetype in self.archetypes.iter() {
+            for (row, &id) in archetype.ids.iter().enumerate() {
+                {% for key, component in components %}
+                    if archetype.component_types.contains(ComponentType::{{ component.name }}) {
+                        {% if component.type %}
+                            buf.append((id, ComponentValue::{{ component.name }}(archetype.{{ key }}[row].clone())));
+                        {% else %}
+                            buf.append((id, ComponentValue::{{ component.name }}));
+                        {% endif %}
+                    }
+                {% endfor %}
+            }
+        }
+    }
+
+    pub fn clone_changes<A: Append<EntityChange>>(&self, buf: &mut A) {
+        for archetype in self.archetypes.iter() {
+            for (row, &id) in archetype.ids.iter().enumerate() {
+                {% for key, component in components %}
+                    if archetype.component_types.contains(ComponentType::{{ component.name }}) {
+                        {% if component.type %}
+                            buf.append(insert::{{ key }}(id, archetype.{{ key }}[row].clone()));
+                        {% else %}
+                            buf.append(insert::{{ key }}(id));
+                        {% endif %}
+                    }
+                {% endfor %}
+            }
+        }
+        {% if resources %}
+        self.clone_resource_changes(buf);
+        {% endif %}
+    }
+
+    /// Appends the changes which, when committed to this store,
+    /// make its contents match `other`. Values of components declared
+    /// with `partial_eq` are only inserted if they differ. Values of
+    /// other components are inserted whenever they are present in `other`.
+    pub fn diff_changes<A: Append<EntityChange>>(&self, other: &EntityStore, buf: &mut A) {
+        for archetype in other.archetypes.iter() {
+            for (row, &id) in archetype.ids.iter().enumerate() {
+                {% for key, component in components %}
+                    {% if component.type %}
+                        if archetype.component_types.contains(ComponentType::{{ component.name }}) {
+                            let value = &archetype.{{ key }}[row];
+                            {% if component.partial_eq %}
+                                if self.get_{{ key }}(id) != Some(value) {
+                                    buf.append(insert::{{ key }}(id, value.clone()));
+                                }
+                            {% else %}
+                                buf.append(insert::{{ key }}(id, value.clone()));
+                            {% endif %}
+                        }
+                    {% else %}
+                        if archetype.component_types.contains(ComponentType::{{ component.name }})
+                            && !self.contains_{{ key }}(id)
+                        {
+                            buf.append(insert::{{ key }}(id));
+                        }
+                    {% endif %}
+                {% endfor %}
+            }
+        }
+        for archetype in self.archetypes.iter() {
+            for &id in archetype.ids.iter() {
+                let other_component_types = other.component_types(id);
+                for component_type in archetype.component_types.iter() {
+                    if !other_component_types.contains(component_type) {
+                        buf.append(EntityChange::Remove(id, component_type));
+                    }
+                }
+            }
+        }
+        {% if resources %}
+        self.diff_resource_changes(other, buf);
+        {% endif %}
+    }
+}
+{% endif %}
@@ -0,0 +1,169 @@
+#![allow(dead_code)]
+
+use super::{EntityChange, EntityStore, EntityWorld};
+{% if unique_errors %}
+use super::UniqueViolation;
+{% endif %}
+
+/// Returns the change which would undo the given change,
+/// if it were committed to the given store. Returns `None`
+/// if the change would have no effect.
+pub fn inverse_change(entity_store: &EntityStore, change: &EntityChange) -> Option<EntityChange> {
+    match change {
+        &EntityChange::Insert(id, ref value) => {
+            let inverse = if let Some(current) = entity_store.get(id, value.typ()) {
+                EntityChange::Insert(id, current.to_value())
+            } else {
+                EntityChange::Remove(id, value.typ())
+            };
+            Some(inverse)
+        }
+        &EntityChange::Remove(id, typ) => {
+            entity_store.get(id, typ).map(|current| {
+                EntityChange::Insert(id, current.to_value())
+            })
+        }
+        {% if resources %}
+        &EntityChange::SetResource(ref value) => {
+            let inverse = if let Some(current) = entity_store.get_resource(value.typ()) {
+                EntityChange::SetResource(current.to_value())
+            } else {
+                EntityChange::ClearResource(value.typ())
+            };
+            Some(inverse)
+        }
+        &EntityChange::ClearResource(typ) => {
+            entity_store.get_resource(typ).map(|current| {
+                EntityChange::SetResource(current.to_value())
+            })
+        }
+        {% endif %}
+    }
+}
+
+/// Records the inverse of each change committed through it,
+/// grouped into transactions, so that transactions can be
+/// undone and redone. Ids freed by `EntityWorld::despawn`
+/// are not tracked, so entities should be removed by committing
+/// changes through the journal if they may be restored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangeJournal {
+    undo: Vec<Vec<EntityChange>>,
+    redo: Vec<Vec<EntityChange>>,
+    current: Vec<EntityChange>,
+}
+
+impl ChangeJournal {
+    pub fn new() -> Self {
+        Self {
+            undo: Vec::new(),
+            redo: Vec::new(),
+            current: Vec::new(),
+        }
+    }
+
+    /// Commits a change to the world, recording its inverse in the
+    /// current transaction. Committing a change discards any
+    /// transactions which could be redone.
+    {%- if unique_errors %}
+    /// Changes rejected by `EntityWorld::commit` are neither
+    /// recorded nor discard any transactions.
+    {%- endif %}
+    pub fn commit(&mut self, world: &mut EntityWorld, change: EntityChange){% if unique_errors %} -> Result<(), UniqueViolation>{% endif %} {
+        {% if unique_errors %}
+        world.entity_store().check_unique(&change)?;
+        {% endif %}
+        self.redo.clear();
+        Self::commit_inverting(world, change, &mut self.current);
+        {% if unique_errors %}
+        Ok(())
+        {% endif %}
+    }
+
+    /// Ends the current transaction. Subsequent changes will be
+    /// undone separately from those committed so far.
+    pub fn end_transaction(&mut self) {
+        if !self.current.is_empty() {
+            let transaction = ::std::mem::take(&mut self.current);
+            self.undo.push(transaction);
+        }
+    }
+
+    /// Reverts the most recent transaction, ending the current
+    /// transaction first. Returns false if there was nothing to undo.
+    pub fn undo(&mut self, world: &mut EntityWorld) -> bool {
+        self.end_transaction();
+        if let Some(transaction) = self.undo.pop() {
+            let redo = Self::apply(world, transaction);
+            self.redo.push(redo);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Reapplies the most recently undone transaction.
+    /// Returns false if there was nothing to redo.
+    pub fn redo(&mut self, world: &mut EntityWorld) -> bool {
+        if let Some(transaction) = self.redo.pop() {
+            let undo = Self::apply(world, transaction);
+            self.undo.push(undo);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty() || !self.current.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.current.clear();
+    }
+
+    // Commits a change, appending the changes which would revert it.
+    fn commit_inverting(world: &mut EntityWorld, change: EntityChange, inverses: &mut Vec<EntityChange>) {
+        {% if unique_components %}
+        if world.entity_store().check_unique(&change).is_err() {
+            // the change will be ignored so has nothing to revert
+            return;
+        }
+        // record the removal of a unique component from its previous owner
+        if let Some(removal) = world.entity_store().implied_removal(&change) {
+            Self::commit_inverting(world, removal, inverses);
+        }
+        {% endif %}
+        if let Some(inverse) = inverse_change(world.entity_store(), &change) {
+            inverses.push(inverse);
+        }
+        {% if unique_errors %}
+        // conflicting changes have already returned, so this can't fail
+        let _ = world.commit(change);
+        {% else %}
+        world.commit(change);
+        {% endif %}
+    }
+
+    // Commits the inverse changes of a transaction in reverse order,
+    // returning the transaction which would revert them.
+    fn apply(world: &mut EntityWorld, transaction: Vec<EntityChange>) -> Vec<EntityChange> {
+        let mut inverse_transaction = Vec::new();
+        for change in transaction.into_iter().rev() {
+            Self::commit_inverting(world, change, &mut inverse_transaction);
+        }
+        inverse_transaction
+    }
+}
+
+impl Default for ChangeJournal {
+    fn default() -> Self {
+        Self::new()
+    }
+}
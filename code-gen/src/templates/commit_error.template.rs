@@ -0,0 +1,84 @@
+#![allow(dead_code)]
+#![allow(unused_imports)]
+#![allow(unused_variables)]
+
+use super::{EntityId, EntityChange, EntityStore, ComponentType, ComponentValue};
+{% if unique_components %}
+use super::UniqueViolation;
+{% endif %}
+{% if resources %}
+use super::ResourceType;
+{% endif %}
+{% if constraints %}
+use super::ConstraintViolation;
+{% endif %}
+
+/// Reasons a change would be ignored when committed, or would
+/// leave the world in a state which is probably unintended.
+#[derive(Debug, Clone)]
+pub enum CommitError {
+    /// The component has no storage, so inserting it has no effect.
+    NotStored(EntityId, ComponentType),
+    /// The component being removed isn't present.
+    AbsentComponent(EntityId, ComponentType),
+    /// The id hasn't been handed out by the world's id allocator,
+    /// or has since been freed.
+    UnallocatedId(EntityId),
+    {% if spatial_hashes %}
+    /// The value of a position component is outside a spatial hash.
+    PositionOutsideGrid(EntityId, ComponentValue),
+    {% endif %}
+    {% if unique_components %}
+    /// A unique component declared with `on_conflict = "error"`
+    /// would be given to a second entity.
+    UniqueViolation(UniqueViolation),
+    {% endif %}
+    {% if resources %}
+    /// The resource being cleared isn't set.
+    AbsentResource(ResourceType),
+    {% endif %}
+    {% if constraints %}
+    /// The entity would be left violating a `requires` or `excludes` constraint.
+    ConstraintViolation(ConstraintViolation),
+    {% endif %}
+}
+
+impl EntityStore {
+    /// Checks whether committing a change to this store would
+    /// be ignored or violate a constraint.
+    pub fn check_commit(&self, change: &EntityChange) -> Result<(), CommitError> {
+        match change {
+            &EntityChange::Insert(id, ref value) => {
+                let component_type = value.typ();
+                if !component_type.is_stored() {
+                    return Err(CommitError::NotStored(id, component_type));
+                }
+                {% if unique_components %}
+                self.check_unique(change).map_err(CommitError::UniqueViolation)?;
+                {% endif %}
+            }
+            &EntityChange::Remove(id, component_type) => {
+                if !self.contains(id, component_type) {
+                    return Err(CommitError::AbsentComponent(id, component_type));
+                }
+            }
+            {% if resources %}
+            &EntityChange::SetResource(_) => {}
+            &EntityChange::ClearResource(resource_type) => {
+                if self.get_resource(resource_type).is_none() {
+                    return Err(CommitError::AbsentResource(resource_type));
+                }
+            }
+            {% endif %}
+        }
+        Ok(())
+    }
+
+    /// Commits a change if `check_commit` succeeds. Otherwise the
+    /// store is left unchanged.
+    pub fn try_commit(&mut self, change: EntityChange) -> Result<(), CommitError> {
+        self.check_commit(&change)?;
+        self.apply(change);
+        Ok(())
+    }
+}
@@ -1,4 +1,7 @@
 #![allow(dead_code)]
+{% if relations %}
+use super::EntityId;
+{% endif %}
 
 enum_from_primitive! {
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -9,6 +12,33 @@ pub enum ComponentType {
 }
 }
 
+impl ComponentType {
+    /// Returns false for components declared without storage,
+    /// which are ignored when inserted.
+    pub fn is_stored(self) -> bool {
+        {% for _, component in components %}
+            {% if not component.storage %}
+                if self == ComponentType::{{ component.name }} {
+                    return false;
+                }
+            {% endif %}
+        {% endfor %}
+        true
+    }
+
+    /// Returns true for components which at most one entity may have.
+    pub fn is_unique(self) -> bool {
+        {% for _, component in components %}
+            {% if component.unique %}
+                if self == ComponentType::{{ component.name }} {
+                    return true;
+                }
+            {% endif %}
+        {% endfor %}
+        false
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ComponentValue {
     {% for _, component in components %}
@@ -56,4 +86,16 @@ impl<'a> ComponentRef<'a> {
             {% endfor %}
         }
     }
+
+    pub fn to_value(&self) -> ComponentValue {
+        match self {
+            {% for _, component in components %}
+                {% if component.type %}
+                    &ComponentRef::{{ component.name }}(value) => ComponentValue::{{ component.name }}(value.clone()),
+                {% else %}
+                    &ComponentRef::{{ component.name }} => ComponentValue::{{ component.name }},
+                {% endif %}
+            {% endfor %}
+        }
+    }
 }
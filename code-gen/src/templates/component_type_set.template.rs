@@ -2,11 +2,14 @@
 
 use enum_primitive::FromPrimitive;
 use super::{constants, ComponentType};
+{% if constraints %}
+use super::EntityId;
+{% endif %}
 
 const BITMAP_BITS: usize = 64;
 const NUM_BITMAPS: usize = 1 + (constants::NUM_COMPONENT_TYPES - 1) / BITMAP_BITS;
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct ComponentTypeSet {
     bitmaps: [u64; NUM_BITMAPS],
 }
@@ -39,7 +42,7 @@ impl ComponentTypeSet {
 
     pub fn contains(&self, component_type: ComponentType) -> bool {
         self.bitmaps[(component_type as usize) / BITMAP_BITS] &
-            (1 << ((component_type as usize % BITMAP_BITS))) != 0
+            (1 << ((component_type as usize) % BITMAP_BITS)) != 0
     }
 
     pub fn iter(&self) -> ComponentTypeSetIter {
@@ -48,7 +51,44 @@ impl ComponentTypeSet {
             index: 0,
         }
     }
+
+    {% if constraints %}
+    /// Checks the `requires` and `excludes` constraints from the spec
+    /// against the component types of the entity with the given id.
+    pub fn check_constraints(&self, id: EntityId) -> Result<(), ConstraintViolation> {
+        {% for constraint in constraints %}
+            if self.contains(ComponentType::{{ constraint.component.name }}) &&
+                {% if constraint.type == "requires" %}!{% endif %}self.contains(ComponentType::{{ constraint.other.name }})
+            {
+                {% if constraint.type == "requires" %}
+                    return Err(ConstraintViolation::Requires(id, ComponentType::{{ constraint.component.name }}, ComponentType::{{ constraint.other.name }}));
+                {% else %}
+                    return Err(ConstraintViolation::Excludes(id, ComponentType::{{ constraint.component.name }}, ComponentType::{{ constraint.other.name }}));
+                {% endif %}
+            }
+        {% endfor %}
+        Ok(())
+    }
+    {% endif %}
+}
+
+impl Default for ComponentTypeSet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+{% if constraints %}
+/// A constraint from the spec which isn't satisfied by an entity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConstraintViolation {
+    /// The entity has the first component but not the second,
+    /// which the first requires.
+    Requires(EntityId, ComponentType, ComponentType),
+    /// The entity has both components, but the first excludes the second.
+    Excludes(EntityId, ComponentType, ComponentType),
 }
+{% endif %}
 
 pub struct ComponentTypeSetIter {
     bitmaps: [u64; NUM_BITMAPS],
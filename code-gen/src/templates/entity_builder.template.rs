@@ -0,0 +1,120 @@
+#![allow(dead_code)]
+#![allow(unused_imports)]
+
+use entity_store_helper::append::Append;
+use super::{EntityId, EntityChange, EntityStore, EntityWorld, ComponentType,
+            ComponentTypeSet, insert};
+{% if unique_errors %}
+use super::UniqueViolation;
+{% endif %}
+
+/// Collects the components of an entity so they can be inserted
+/// together. Building doesn't consume the builder, so one can be
+/// kept as a template and applied to any number of entities.
+#[derive(Debug, Clone)]
+pub struct EntityBuilder {
+    component_types: ComponentTypeSet,
+    {% for key, component in components %}
+        {% if component.type %}
+            {{ key }}: Option<{{ component.type }}>,
+        {% endif %}
+    {% endfor %}
+}
+
+impl EntityBuilder {
+    pub fn new() -> Self {
+        Self {
+            component_types: ComponentTypeSet::new(),
+            {% for key, component in components %}
+                {% if component.type %}
+                    {{ key }}: None,
+                {% endif %}
+            {% endfor %}
+        }
+    }
+
+    pub fn component_types(&self) -> ComponentTypeSet {
+        self.component_types
+    }
+
+    {% for key, component in components %}
+        {% if component.type %}
+        pub fn {{ key }}(mut self, value: {{ component.type }}) -> Self {
+            self.component_types.insert(ComponentType::{{ component.name }});
+            self.{{ key }} = Some(value);
+            self
+        }
+        {% else %}
+        pub fn {{ key }}(mut self) -> Self {
+            self.component_types.insert(ComponentType::{{ component.name }});
+            self
+        }
+        {% endif %}
+    {% endfor %}
+
+    /// Appends the changes which insert each component of the
+    /// builder into the entity with the given id.
+    pub fn append_changes<A: Append<EntityChange>>(&self, id: EntityId, buf: &mut A) {
+        {% for key, component in components %}
+            {% if component.type %}
+                if let Some(ref value) = self.{{ key }} {
+                    buf.append(insert::{{ key }}(id, value.clone()));
+                }
+            {% else %}
+                if self.component_types.contains(ComponentType::{{ component.name }}) {
+                    buf.append(insert::{{ key }}(id));
+                }
+            {% endif %}
+        {% endfor %}
+    }
+
+    pub fn changes(&self, id: EntityId) -> Vec<EntityChange> {
+        let mut changes = Vec::new();
+        self.append_changes(id, &mut changes);
+        changes
+    }
+
+    {% if unique_errors %}
+    /// Inserts the components of the builder into an entity, stopping
+    /// at the first unique component which another entity already has.
+    pub fn commit(&self, id: EntityId, entity_store: &mut EntityStore) -> Result<(), UniqueViolation> {
+        for change in self.changes(id) {
+            entity_store.commit(change)?;
+        }
+        Ok(())
+    }
+
+    /// Allocates a new entity in the world and inserts the
+    /// components of the builder into it, stopping at the first
+    /// unique component which another entity already has.
+    pub fn spawn(&self, world: &mut EntityWorld) -> Result<EntityId, UniqueViolation> {
+        let id = world.spawn();
+        for change in self.changes(id) {
+            world.commit(change)?;
+        }
+        Ok(id)
+    }
+    {% else %}
+    pub fn commit(&self, id: EntityId, entity_store: &mut EntityStore) {
+        for change in self.changes(id) {
+            entity_store.commit(change);
+        }
+    }
+
+    /// Allocates a new entity in the world and inserts the
+    /// components of the builder into it.
+    pub fn spawn(&self, world: &mut EntityWorld) -> EntityId {
+        let id = world.spawn();
+        for change in self.changes(id) {
+            world.commit(change);
+        }
+        id
+    }
+    {% endif %}
+}
+
+impl Default for EntityBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
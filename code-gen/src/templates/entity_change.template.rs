@@ -1,27 +1,68 @@
 #![allow(dead_code)]
 
 use super::{ComponentValue, ComponentType, EntityId};
+{% if resources %}
+use super::{ResourceValue, ResourceType};
+{% endif %}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum EntityChange {
     Insert(EntityId, ComponentValue),
     Remove(EntityId, ComponentType),
+    {% if resources %}
+    SetResource(ResourceValue),
+    ClearResource(ResourceType),
+    {% endif %}
 }
 
 impl EntityChange {
+    /// The entity whose component is changed.
+    {%- if resources %}
+    ///
+    /// # Panics
+    ///
+    /// Panics if a resource is changed, which can be checked with
+    /// `resource_typ`.
+    {%- endif %}
     pub fn id(&self) -> EntityId {
         match self {
             &EntityChange::Insert(id, ..) => id,
             &EntityChange::Remove(id, ..) => id,
+            {% if resources %}
+            _ => panic!("resource changes have no entity: {:?}", self),
+            {% endif %}
         }
     }
 
+    /// The type of component which is changed.
+    {%- if resources %}
+    ///
+    /// # Panics
+    ///
+    /// Panics if a resource is changed, which can be checked with
+    /// `resource_typ`.
+    {%- endif %}
     pub fn typ(&self) -> ComponentType {
         match self {
             &EntityChange::Insert(_, ref value) => value.typ(),
             &EntityChange::Remove(_, typ) => typ,
+            {% if resources %}
+            _ => panic!("resource changes have no component type: {:?}", self),
+            {% endif %}
         }
     }
+    {% if resources %}
+
+    /// The type of resource which is changed, or `None` if a component
+    /// is changed.
+    pub fn resource_typ(&self) -> Option<ResourceType> {
+        match self {
+            &EntityChange::SetResource(ref value) => Some(value.typ()),
+            &EntityChange::ClearResource(typ) => Some(typ),
+            _ => None,
+        }
+    }
+    {% endif %}
 }
 
 pub mod insert {
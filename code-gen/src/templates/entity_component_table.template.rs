@@ -1,7 +1,17 @@
 #![allow(dead_code)]
 
 use super::{EntityVecMap, ComponentTypeSet, ComponentTypeSetIter, EntityChange,
-            EntityId, EntityStore, ComponentRefIter, ComponentDrain, ComponentDrainInsert};
+            EntityId, EntityStore, ComponentRefIter, ComponentDrain, ComponentDrainInsert,
+            CloneEntity};
+{% if relations %}
+use super::{EntityVecSet, remove};
+{% endif %}
+{% if constraints %}
+use super::ConstraintViolation;
+{% endif %}
+{% if unique_components %}
+use super::ComponentType;
+{% endif %}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EntityComponentTable(EntityVecMap<ComponentTypeSet>);
@@ -10,36 +20,134 @@ impl EntityComponentTable {
     pub fn new() -> Self {
         EntityComponentTable(EntityVecMap::new())
     }
+    {% if unique_components %}
+    /// Updates the table for a change which is about to be committed.
+    /// Giving a unique component to a different entity removes it from
+    /// its previous owner, or is ignored if the component is declared
+    /// with `on_conflict = "error"`, as in the store.
+    {%- endif %}
     pub fn update(&mut self, change: &EntityChange) {
         match change {
             &EntityChange::Insert(id, ref value) => {
-                self.0.entry(&id).or_insert_with(|| ComponentTypeSet::new()).insert(value.typ());
+                {% if unique_components %}
+                let typ = value.typ();
+                if let Some(owner) = self.unique_owner(id, typ) {
+                    match typ {
+                        {% for component in unique_components %}
+                            {% if component.unique == "error" %}
+                                ComponentType::{{ component.name }} => return,
+                            {% endif %}
+                        {% endfor %}
+                        _ => self.update(&EntityChange::Remove(owner, typ)),
+                    }
+                }
+                {% endif %}
+                self.0.entry(&id).or_insert_with(ComponentTypeSet::new).insert(value.typ());
             }
             &EntityChange::Remove(id, typ) => {
                 if let Some(set) = self.0.get_mut(&id) {
                     set.remove(typ);
                 }
             }
+            {% if resources %}
+            _ => {}
+            {% endif %}
+        }
+    }
+
+    {% if unique_components %}
+    // Returns the entity other than `id` which has the given component,
+    // if it's unique.
+    fn unique_owner(&self, id: EntityId, component_type: ComponentType) -> Option<EntityId> {
+        match component_type {
+            {% for component in unique_components %}
+                ComponentType::{{ component.name }} => (),
+            {% endfor %}
+            _ => return None,
         }
+        self.0.iter()
+            .find(|&(owner, set)| owner != id && set.contains(component_type))
+            .map(|(owner, _)| owner)
     }
 
+    {% endif %}
     pub fn get(&self, id: EntityId) -> ComponentTypeSet {
-        self.0.get(&id).cloned().unwrap_or_else(ComponentTypeSet::new)
+        self.0.get(&id).cloned().unwrap_or_default()
     }
 
     pub fn component_types(&self, id: EntityId) -> ComponentTypeSetIter {
         self.0.get(&id)
             .map(|s| s.iter())
-            .unwrap_or_else(|| ComponentTypeSetIter::empty())
+            .unwrap_or_else(ComponentTypeSetIter::empty)
+    }
+
+    {% if constraints %}
+    pub fn check_entity_constraints(&self, id: EntityId) -> Result<(), ConstraintViolation> {
+        self.get(id).check_constraints(id)
+    }
+
+    /// Checks the constraints from the spec against every entity,
+    /// returning the first violation found.
+    pub fn check_constraints(&self) -> Result<(), ConstraintViolation> {
+        for (id, set) in self.0.iter() {
+            set.check_constraints(id)?;
+        }
+        Ok(())
     }
+    {% endif %}
 
+    /// Returns the changes which remove all components of an entity.
     pub fn remove_entity(&self, id: EntityId) -> RemoveEntityIter {
         RemoveEntityIter {
             id,
             iter: self.component_types(id),
+            {% if relations %}
+            related: Vec::new().into_iter(),
+            cascaded_ids: Vec::new(),
+            {% endif %}
         }
     }
 
+    {% if relations %}
+    /// Returns the changes which remove all components of an entity,
+    /// followed by the changes made to related entities according to
+    /// the cascade option of each relation.
+    pub fn remove_entity_cascading(&self, id: EntityId, entity_store: &EntityStore) -> RemoveEntityIter {
+        let mut visited = EntityVecSet::new();
+        visited.insert(id);
+        let mut related = Vec::new();
+        let mut cascaded_ids = Vec::new();
+        self.cascade(id, entity_store, &mut visited, &mut related, &mut cascaded_ids);
+        RemoveEntityIter {
+            id,
+            iter: self.component_types(id),
+            related: related.into_iter(),
+            cascaded_ids,
+        }
+    }
+
+    fn cascade(&self, id: EntityId, entity_store: &EntityStore, visited: &mut EntityVecSet,
+               related: &mut Vec<EntityChange>, cascaded_ids: &mut Vec<EntityId>) {
+        {% for key, relation in relations %}
+            {% if relation.cascade == "detach" %}
+                for &child in entity_store.children_{{ key }}(id) {
+                    related.push(remove::{{ key }}(child));
+                }
+            {% elif relation.cascade == "remove" %}
+                for &child in entity_store.children_{{ key }}(id) {
+                    // guard against cycles
+                    if visited.insert(child) {
+                        continue;
+                    }
+                    related.extend(self.component_types(child).map(|t| EntityChange::Remove(child, t)));
+                    cascaded_ids.push(child);
+                    self.cascade(child, entity_store, visited, related, cascaded_ids);
+                }
+            {% endif %}
+        {% endfor %}
+    }
+    {% endif %}
+
     pub fn component_ref_iter<'a>(&self, id: EntityId, entity_store: &'a EntityStore) -> ComponentRefIter<'a> {
         entity_store.component_ref_iter(id, self.component_types(id))
     }
@@ -51,16 +159,48 @@ impl EntityComponentTable {
     pub fn component_drain_insert<'a>(&self, source_id: EntityId, dest_id: EntityId, entity_store: &'a mut EntityStore) -> ComponentDrainInsert<'a> {
         entity_store.component_drain_insert(source_id, dest_id, self.component_types(source_id))
     }
+
+    pub fn clone_entity<'a>(&self, source_id: EntityId, dest_id: EntityId, entity_store: &'a EntityStore) -> CloneEntity<'a> {
+        entity_store.clone_entity(source_id, dest_id, self.component_types(source_id))
+    }
+}
+
+impl Default for EntityComponentTable {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 pub struct RemoveEntityIter {
     id: EntityId,
     iter: ComponentTypeSetIter,
+    {% if relations %}
+    related: ::std::vec::IntoIter<EntityChange>,
+    cascaded_ids: Vec<EntityId>,
+    {% endif %}
+}
+
+{% if relations %}
+impl RemoveEntityIter {
+    /// The ids of the entities removed by relations with
+    /// `cascade = "remove"`, in addition to the entity itself.
+    /// This is empty unless the iterator came from
+    /// `remove_entity_cascading`.
+    pub fn cascaded_ids(&self) -> &[EntityId] {
+        &self.cascaded_ids
+    }
 }
+{% endif %}
 
 impl Iterator for RemoveEntityIter {
     type Item = EntityChange;
     fn next(&mut self) -> Option<Self::Item> {
+        {% if relations %}
+        let id = self.id;
+        let related = &mut self.related;
+        self.iter.next().map(|t| EntityChange::Remove(id, t)).or_else(|| related.next())
+        {% else %}
         self.iter.next().map(|t| EntityChange::Remove(self.id, t))
+        {% endif %}
     }
 }
@@ -0,0 +1,161 @@
+#![allow(dead_code)]
+#![allow(unused_imports)]
+
+use super::{EntityId, EntityChange, EntityStore, ComponentValue, ComponentType};
+{% if unique_errors %}
+use super::UniqueViolation;
+{% endif %}
+
+/// Callbacks invoked when changes are committed with
+/// `EntityStore::commit_with_hooks`. Insert hooks receive the
+/// id, the new value and the value it replaced. Remove hooks
+/// receive the id and the value which was removed. Hooks on
+/// flag components receive only the id, and in the case of
+/// insert hooks, whether the flag was already present.
+pub struct EntityHooks<'a> {
+    {% for key, component in components %}
+        {% if component.storage %}
+            {% if component.type %}
+                insert_{{ key }}: Vec<Box<dyn FnMut(EntityId, &{{ component.type }}, Option<&{{ component.type }}>) + 'a>>,
+                remove_{{ key }}: Vec<Box<dyn FnMut(EntityId, &{{ component.type }}) + 'a>>,
+            {% else %}
+                insert_{{ key }}: Vec<Box<dyn FnMut(EntityId, bool) + 'a>>,
+                remove_{{ key }}: Vec<Box<dyn FnMut(EntityId) + 'a>>,
+            {% endif %}
+        {% endif %}
+    {% endfor %}
+}
+
+impl<'a> EntityHooks<'a> {
+    pub fn new() -> Self {
+        Self {
+            {% for key, component in components %}
+                {% if component.storage %}
+                    insert_{{ key }}: Vec::new(),
+                    remove_{{ key }}: Vec::new(),
+                {% endif %}
+            {% endfor %}
+        }
+    }
+
+    {% for key, component in components %}
+        {% if component.storage %}
+            {% if component.type %}
+    pub fn on_insert_{{ key }}<F: FnMut(EntityId, &{{ component.type }}, Option<&{{ component.type }}>) + 'a>(&mut self, f: F) {
+        self.insert_{{ key }}.push(Box::new(f));
+    }
+
+    pub fn on_remove_{{ key }}<F: FnMut(EntityId, &{{ component.type }}) + 'a>(&mut self, f: F) {
+        self.remove_{{ key }}.push(Box::new(f));
+    }
+            {% else %}
+    pub fn on_insert_{{ key }}<F: FnMut(EntityId, bool) + 'a>(&mut self, f: F) {
+        self.insert_{{ key }}.push(Box::new(f));
+    }
+
+    pub fn on_remove_{{ key }}<F: FnMut(EntityId) + 'a>(&mut self, f: F) {
+        self.remove_{{ key }}.push(Box::new(f));
+    }
+            {% endif %}
+        {% endif %}
+    {% endfor %}
+}
+
+impl<'a> Default for EntityHooks<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EntityStore {
+    /// Commits a change, then invokes any hooks registered for
+    /// the type of component which changed.
+    {%- if unique_errors %}
+    /// As with `commit`, changes which would give a unique component
+    /// declared with `on_conflict = "error"` to a second entity are
+    /// rejected.
+    {%- endif %}
+    pub fn commit_with_hooks(&mut self, change: EntityChange, hooks: &mut EntityHooks){% if unique_errors %} -> Result<(), UniqueViolation>{% endif %} {
+        {% if unique_errors %}
+        self.check_unique(&change)?;
+        self.apply_with_hooks(change, hooks);
+        Ok(())
+        {% else %}
+        self.apply_with_hooks(change, hooks);
+        {% endif %}
+    }
+
+    // Applies a change which is known not to violate a unique
+    // component, then invokes the relevant hooks.
+    pub(super) fn apply_with_hooks(&mut self, change: EntityChange, hooks: &mut EntityHooks) {
+        {% if unique_components %}
+        // invoke the hooks for the removal of a unique component from its previous owner
+        if let Some(removal) = self.implied_removal(&change) {
+            self.apply_with_hooks(removal, hooks);
+        }
+        {% endif %}
+        match change {
+            EntityChange::Insert(id, value) => match value {
+                {% for key, component in components %}
+                    {% if component.storage %}
+                        {% if component.type %}
+                            ComponentValue::{{ component.name }}(value) => {
+                                let previous = match self.insert(id, ComponentValue::{{ component.name }}(value)) {
+                                    Some(ComponentValue::{{ component.name }}(previous)) => Some(previous),
+                                    _ => None,
+                                };
+                                if let Some(current) = self.get_{{ key }}(id) {
+                                    for f in hooks.insert_{{ key }}.iter_mut() {
+                                        f(id, current, previous.as_ref());
+                                    }
+                                }
+                            }
+                        {% else %}
+                            ComponentValue::{{ component.name }} => {
+                                let existed = self.contains_{{ key }}(id);
+                                self.insert(id, ComponentValue::{{ component.name }});
+                                if self.contains_{{ key }}(id) {
+                                    for f in hooks.insert_{{ key }}.iter_mut() {
+                                        f(id, existed);
+                                    }
+                                }
+                            }
+                        {% endif %}
+                    {% else %}
+                        {% if component.type %}
+                            ComponentValue::{{ component.name }}(_) => {}
+                        {% else %}
+                            ComponentValue::{{ component.name }} => {}
+                        {% endif %}
+                    {% endif %}
+                {% endfor %}
+            }
+            EntityChange::Remove(id, typ) => match typ {
+                {% for key, component in components %}
+                    {% if component.storage %}
+                        ComponentType::{{ component.name }} => {
+                            {% if component.type %}
+                                if let Some(ComponentValue::{{ component.name }}(previous)) = self.remove(id, typ) {
+                                    for f in hooks.remove_{{ key }}.iter_mut() {
+                                        f(id, &previous);
+                                    }
+                                }
+                            {% else %}
+                                if self.remove(id, typ).is_some() {
+                                    for f in hooks.remove_{{ key }}.iter_mut() {
+                                        f(id);
+                                    }
+                                }
+                            {% endif %}
+                        }
+                    {% else %}
+                        ComponentType::{{ component.name }} => {}
+                    {% endif %}
+                {% endfor %}
+            }
+            {% if resources %}
+            change => self.apply(change),
+            {% endif %}
+        }
+    }
+}
@@ -0,0 +1,279 @@
+#![allow(dead_code)]
+
+use std::mem;
+use std::slice;
+use std::iter;
+use super::{EntityId, entity_id_index, entity_id_is_stale};
+
+/// Map from entity ids to values, with values stored contiguously
+/// in a dense array. An index into the dense array is stored for
+/// each entity id in a sparse array. Entries in the sparse array
+/// aren't cleared when values are removed, so an entry is only
+/// valid if the dense array has the same id at that position.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntitySparseSetMap<T> {
+    sparse: Vec<usize>,
+    ids: Vec<EntityId>,
+    values: Vec<T>,
+}
+
+impl<T> EntitySparseSetMap<T> {
+    pub fn new() -> Self {
+        EntitySparseSetMap {
+            sparse: Vec::new(),
+            ids: Vec::new(),
+            values: Vec::new(),
+        }
+    }
+
+    // Returns the position in the dense arrays of the entry
+    // whose id has the same index as the given id, if any.
+    fn dense_index(&self, id: &EntityId) -> Option<usize> {
+        self.sparse.get(entity_id_index(*id)).and_then(|&dense_index| {
+            self.ids.get(dense_index).and_then(|dense_id| {
+                if entity_id_index(*dense_id) == entity_id_index(*id) {
+                    Some(dense_index)
+                } else {
+                    None
+                }
+            })
+        })
+    }
+
+    fn find(&self, id: &EntityId) -> Option<usize> {
+        self.dense_index(id).and_then(|dense_index| {
+            if self.ids[dense_index] == *id {
+                Some(dense_index)
+            } else {
+                None
+            }
+        })
+    }
+
+    pub fn insert(&mut self, id: EntityId, component: T) -> Option<T> {
+        if let Some(dense_index) = self.dense_index(&id) {
+            if self.ids[dense_index] == id {
+                return Some(mem::replace(&mut self.values[dense_index], component));
+            }
+            if entity_id_is_stale(id, self.ids[dense_index]) {
+                // the id belongs to an entity which has since been freed
+                return None;
+            }
+            // any value in this slot belongs to a freed entity
+            self.ids[dense_index] = id;
+            self.values[dense_index] = component;
+            return None;
+        }
+
+        let index = entity_id_index(id);
+        if index >= self.sparse.len() {
+            self.sparse.resize(index + 1, 0);
+        }
+        self.sparse[index] = self.ids.len();
+        self.ids.push(id);
+        self.values.push(component);
+
+        None
+    }
+
+    pub fn remove(&mut self, id: &EntityId) -> Option<T> {
+        self.find(id).map(|dense_index| {
+            self.ids.swap_remove(dense_index);
+            if let Some(moved_id) = self.ids.get(dense_index) {
+                self.sparse[entity_id_index(*moved_id)] = dense_index;
+            }
+            self.values.swap_remove(dense_index)
+        })
+    }
+
+    pub fn get(&self, id: &EntityId) -> Option<&T> {
+        self.find(id).map(|dense_index| &self.values[dense_index])
+    }
+
+    pub fn get_mut(&mut self, id: &EntityId) -> Option<&mut T> {
+        self.find(id).map(move |dense_index| &mut self.values[dense_index])
+    }
+
+    pub fn clear(&mut self) {
+        self.sparse.clear();
+        self.ids.clear();
+        self.values.clear();
+    }
+
+    pub fn contains_key(&self, id: &EntityId) -> bool {
+        self.find(id).is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    pub fn iter(&self) -> EntitySparseSetMapIter<'_, T> {
+        EntitySparseSetMapIter {
+            iter: self.ids.iter().zip(self.values.iter()),
+        }
+    }
+
+    pub fn entry(&mut self, id: &EntityId) -> EntitySparseSetMapEntry<'_, T> {
+        if let Some(dense_index) = self.find(id) {
+            EntitySparseSetMapEntry::Occupied(&mut self.values[dense_index])
+        } else {
+            EntitySparseSetMapEntry::Vacant {
+                map: self,
+                id: *id,
+            }
+        }
+    }
+}
+
+impl<T> Default for EntitySparseSetMap<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct EntitySparseSetMapIter<'a, T: 'a> {
+    iter: iter::Zip<slice::Iter<'a, EntityId>, slice::Iter<'a, T>>,
+}
+
+impl<'a, T: 'a> Iterator for EntitySparseSetMapIter<'a, T> {
+    type Item = (EntityId, &'a T);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(id, value)| (*id, value))
+    }
+}
+
+pub enum EntitySparseSetMapEntry<'a, T: 'a> {
+    Occupied(&'a mut T),
+    Vacant {
+        map: &'a mut EntitySparseSetMap<T>,
+        id: EntityId,
+    },
+}
+
+impl<'a, T> EntitySparseSetMapEntry<'a, T> {
+    pub fn or_insert(self, default: T) -> &'a mut T {
+        self.or_insert_with(|| default)
+    }
+    pub fn or_insert_with<F: FnOnce() -> T>(self, default: F) -> &'a mut T {
+        match self {
+            EntitySparseSetMapEntry::Occupied(v) => v,
+            EntitySparseSetMapEntry::Vacant { map, id } => {
+                map.insert(id, default());
+                map.get_mut(&id).expect("Failed to insert into EntitySparseSetMap with out of date id")
+            }
+        }
+    }
+}
+
+/// Set of entity ids stored contiguously in a dense array,
+/// indexed by a sparse array as in `EntitySparseSetMap`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntitySparseSet {
+    sparse: Vec<usize>,
+    ids: Vec<EntityId>,
+}
+
+impl EntitySparseSet {
+    pub fn new() -> Self {
+        EntitySparseSet {
+            sparse: Vec::new(),
+            ids: Vec::new(),
+        }
+    }
+
+    fn dense_index(&self, id: &EntityId) -> Option<usize> {
+        self.sparse.get(entity_id_index(*id)).and_then(|&dense_index| {
+            self.ids.get(dense_index).and_then(|dense_id| {
+                if entity_id_index(*dense_id) == entity_id_index(*id) {
+                    Some(dense_index)
+                } else {
+                    None
+                }
+            })
+        })
+    }
+
+    /// Returns true if the id was already present.
+    pub fn insert(&mut self, id: EntityId) -> bool {
+        if let Some(dense_index) = self.dense_index(&id) {
+            if self.ids[dense_index] == id {
+                return true;
+            }
+            if entity_id_is_stale(id, self.ids[dense_index]) {
+                return false;
+            }
+            // the slot belongs to an entity which has since been freed
+            self.ids[dense_index] = id;
+            return false;
+        }
+
+        let index = entity_id_index(id);
+        if index >= self.sparse.len() {
+            self.sparse.resize(index + 1, 0);
+        }
+        self.sparse[index] = self.ids.len();
+        self.ids.push(id);
+
+        false
+    }
+
+    /// Returns true if the id was present.
+    pub fn remove(&mut self, id: &EntityId) -> bool {
+        if !self.contains(id) {
+            return false;
+        }
+
+        let dense_index = self.sparse[entity_id_index(*id)];
+        self.ids.swap_remove(dense_index);
+        if let Some(moved_id) = self.ids.get(dense_index) {
+            self.sparse[entity_id_index(*moved_id)] = dense_index;
+        }
+
+        true
+    }
+
+    pub fn contains(&self, id: &EntityId) -> bool {
+        self.dense_index(id).map(|dense_index| self.ids[dense_index] == *id).unwrap_or(false)
+    }
+
+    pub fn clear(&mut self) {
+        self.sparse.clear();
+        self.ids.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    pub fn iter(&self) -> EntitySparseSetIter<'_> {
+        EntitySparseSetIter {
+            iter: self.ids.iter(),
+        }
+    }
+}
+
+impl Default for EntitySparseSet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct EntitySparseSetIter<'a> {
+    iter: slice::Iter<'a, EntityId>,
+}
+
+impl<'a> Iterator for EntitySparseSetIter<'a> {
+    type Item = EntityId;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().cloned()
+    }
+}
@@ -1,15 +1,34 @@
 #![allow(dead_code)]
 #![allow(unused_imports)]
 use std::collections::{HashMap, HashSet, BTreeMap, BTreeSet};
-use super::{EntityId, EntityVecMap, EntityVecSet, EntityChange, ComponentValue, ComponentRef,
-            ComponentType, ComponentTypeSetIter, EntityComponentTable, insert};
+use std::ops::RangeBounds;
+use super::{EntityId, EntityVecMap, EntityVecSet, EntitySparseSetMap, EntitySparseSet, EntityChange, ComponentValue, ComponentRef,
+            ComponentType, ComponentTypeSetIter, EntityComponentTable, insert, remove};
 use entity_store_helper::append::Append;
+{% if layout == "archetype" %}
+use super::EntityStore;
+{% endif %}
+{% if resources %}
+use super::{Resources, ResourceType, ResourceValue, ResourceRef, set, clear};
+{% endif %}
 
 pub type EntityHashMap<T> = HashMap<EntityId, T>;
 pub type EntityBTreeMap<T> = BTreeMap<EntityId, T>;
 pub type EntityHashSet = HashSet<EntityId>;
 pub type EntityBTreeSet = BTreeSet<EntityId>;
 
+{% if unique_components %}
+/// An attempt to give a unique component to an entity
+/// while another entity has that component.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UniqueViolation {
+    pub component_type: ComponentType,
+    pub owner: EntityId,
+    pub id: EntityId,
+}
+{% endif %}
+
+{% if layout == "component" %}
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EntityStore {
     {% for key, component in components %}
@@ -19,6 +38,28 @@ pub struct EntityStore {
             {% else %}
                 pub {{ key }}: {{ component.storage.rust_type }},
             {% endif %}
+            {% if component.track_changes %}
+                #[serde(skip)]
+                added_{{ key }}: {{ component.storage.set_type }},
+                #[serde(skip)]
+                changed_{{ key }}: {{ component.storage.set_type }},
+                #[serde(skip)]
+                removed_{{ key }}: {{ component.storage.set_type }},
+            {% endif %}
+        {% endif %}
+    {% endfor %}
+    {% if resources %}
+    resources: Resources,
+    {% endif %}
+    {% for key, relation in relations %}
+    children_{{ key }}: EntityHashMap<Vec<EntityId>>,
+    {% endfor %}
+    {% for key, component in components %}
+        {% if component.value_index %}
+    index_{{ key }}: {{ component.value_index.map_type }}<{{ component.type }}, {{ component.value_index.set_type }}>,
+        {% endif %}
+        {% if component.unique %}
+    owner_{{ key }}: Option<EntityId>,
         {% endif %}
     {% endfor %}
 }
@@ -26,20 +67,93 @@ pub struct EntityStore {
 impl EntityStore {
     pub fn new() -> Self {
         Self {
+            {% if resources %}
+            resources: Resources::default(),
+            {% endif %}
+            {% for key, relation in relations %}
+            children_{{ key }}: EntityHashMap::default(),
+            {% endfor %}
+            {% for key, component in components %}
+                {% if component.value_index %}
+                    index_{{ key }}: {{ component.value_index.map_type }}::default(),
+                {% endif %}
+                {% if component.unique %}
+                    owner_{{ key }}: None,
+                {% endif %}
+            {% endfor %}
             {% for key, component in components %}
                 {% if component.storage %}
                     {{ key }}: {{ component.storage.rust_type }}::default(),
+                    {% if component.track_changes %}
+                        added_{{ key }}: {{ component.storage.set_type }}::default(),
+                        changed_{{ key }}: {{ component.storage.set_type }}::default(),
+                        removed_{{ key }}: {{ component.storage.set_type }}::default(),
+                    {% endif %}
                 {% endif %}
             {% endfor %}
         }
     }
 
-    pub fn commit(&mut self, change: EntityChange) {
+    {% for key, component in components %}
+        {% if component.storage %}
+            {% if component.type %}
+    pub fn get_{{ key }}(&self, id: EntityId) -> Option<&{{ component.type }}> {
+        self.{{ key }}.get(&id)
+    }
+            {% endif %}
+
+    pub fn contains_{{ key }}(&self, id: EntityId) -> bool {
+        self.{{ key }}.{{ component.contains }}(&id)
+    }
+        {% endif %}
+    {% endfor %}
+
+    /// Applies a change to the store.
+    {%- if unique_errors %}
+    /// Changes which would give a unique component declared with
+    /// `on_conflict = "error"` to a second entity are rejected,
+    /// leaving the store unchanged.
+    {%- endif %}
+    pub fn commit(&mut self, change: EntityChange){% if unique_errors %} -> Result<(), UniqueViolation>{% endif %} {
+        {% if unique_errors %}
+        self.check_unique(&change)?;
+        self.apply(change);
+        Ok(())
+        {% else %}
+        self.apply(change);
+        {% endif %}
+    }
+
+    // Applies a change which is known not to violate a unique component.
+    pub(super) fn apply(&mut self, change: EntityChange) {
         match change {
             EntityChange::Insert(id, value) => match value {
                 {% for key, component in components %}
                     {% if component.storage %}
-                        {% if component.type %}
+                        {% if component.relation or component.value_index or component.unique %}
+                            {% if component.type %}
+                                ComponentValue::{{ component.name }}(value) => {
+                                    self.insert(id, ComponentValue::{{ component.name }}(value));
+                                }
+                            {% else %}
+                                ComponentValue::{{ component.name }} => {
+                                    self.insert(id, ComponentValue::{{ component.name }});
+                                }
+                            {% endif %}
+                        {% elif component.track_changes %}
+                            {% if component.type %}
+                                ComponentValue::{{ component.name }}(value) => {
+                                    let existed = self.{{ key }}.insert(id, value).is_some();
+                                    self.track_insert_{{ key }}(id, existed);
+                                }
+                            {% else %}
+                                ComponentValue::{{ component.name }} => {
+                                    let existed = self.{{ key }}.contains(&id);
+                                    self.{{ key }}.insert(id);
+                                    self.track_insert_{{ key }}(id, existed);
+                                }
+                            {% endif %}
+                        {% elif component.type %}
                             ComponentValue::{{ component.name }}(value) => { self.{{ key }}.insert(id, value); }
                         {% else %}
                             ComponentValue::{{ component.name }} => { self.{{ key }}.insert(id); }
@@ -55,21 +169,38 @@ impl EntityStore {
             }
             EntityChange::Remove(id, typ) => match typ {
                 {% for key, component in components %}
-                    {% if component.storage %}
+                    {% if component.relation or component.value_index or component.unique %}
+                        ComponentType::{{ component.name }} => { self.remove(id, typ); }
+                    {% elif component.track_changes %}
+                        ComponentType::{{ component.name }} => {
+                            {% if component.type %}
+                                let existed = self.{{ key }}.remove(&id).is_some();
+                            {% else %}
+                                let existed = self.{{ key }}.remove(&id);
+                            {% endif %}
+                            self.track_remove_{{ key }}(id, existed);
+                        }
+                    {% elif component.storage %}
                         ComponentType::{{ component.name }} => { self.{{ key }}.remove(&id); }
                     {% else %}
                         ComponentType::{{ component.name }} => {}
                     {% endif %}
                 {% endfor %}
             }
+            {% if resources %}
+            EntityChange::SetResource(value) => { self.set_resource(value); }
+            EntityChange::ClearResource(typ) => { self.clear_resource(typ); }
+            {% endif %}
         }
     }
 
-    pub fn get(&self, id: EntityId, component_type: ComponentType) -> Option<ComponentRef> {
+    pub fn get(&self, id: EntityId, component_type: ComponentType) -> Option<ComponentRef<'_>> {
         match component_type {
             {% for key, component in components %}
                 ComponentType::{{ component.name }} => {
-                    {% if component.type %}
+                    {% if not component.storage %}
+                        None
+                    {% elif component.type %}
                         self.{{ key }}.get(&id).map(ComponentRef::{{ component.name }})
                     {% else %}
                         if self.{{ key }}.contains(&id) {
@@ -87,7 +218,9 @@ impl EntityStore {
         match component_type {
             {% for key, component in components %}
                 ComponentType::{{ component.name }} => {
-                    {% if component.type %}
+                    {% if not component.storage %}
+                        false
+                    {% elif component.type %}
                         self.{{ key }}.contains_key(&id)
                     {% else %}
                         self.{{ key }}.contains(&id)
@@ -101,10 +234,32 @@ impl EntityStore {
         match component_type {
             {% for key, component in components %}
                 ComponentType::{{ component.name }} => {
-                    {% if component.type %}
-                        self.{{ key }}.remove(&id).map(ComponentValue::{{ component.name }})
+                    {% if not component.storage %}
+                        None
+                    {% elif component.type %}
+                        let previous = self.{{ key }}.remove(&id);
+                        {% if component.track_changes %}
+                            self.track_remove_{{ key }}(id, previous.is_some());
+                        {% endif %}
+                        {% if component.relation %}
+                            self.relate_{{ key }}(id, previous);
+                        {% endif %}
+                        {% if component.value_index %}
+                            self.reindex_{{ key }}(id, previous.as_ref());
+                        {% endif %}
+                        {% if component.unique %}
+                            self.update_owner(id, component_type);
+                        {% endif %}
+                        previous.map(ComponentValue::{{ component.name }})
                     {% else %}
-                        if self.{{ key }}.remove(&id) {
+                        let previous = self.{{ key }}.remove(&id);
+                        {% if component.track_changes %}
+                            self.track_remove_{{ key }}(id, previous);
+                        {% endif %}
+                        {% if component.unique %}
+                            self.update_owner(id, component_type);
+                        {% endif %}
+                        if previous {
                             Some(ComponentValue::{{ component.name }})
                         } else {
                             None
@@ -118,13 +273,50 @@ impl EntityStore {
     pub fn insert(&mut self, id: EntityId, component_value: ComponentValue) -> Option<ComponentValue> {
         match component_value {
             {% for key, component in components %}
-                {% if component.type %}
+                {% if not component.storage %}
+                    {% if component.type %}
+                        ComponentValue::{{ component.name }}(_) => None,
+                    {% else %}
+                        ComponentValue::{{ component.name }} => None,
+                    {% endif %}
+                {% elif component.type %}
                     ComponentValue::{{ component.name }}(value) => {
-                        self.{{ key }}.insert(id, value).map(ComponentValue::{{ component.name }})
+                        {% if component.unique %}
+                            if !self.claim(id, ComponentType::{{ component.name }}) {
+                                return None;
+                            }
+                        {% endif %}
+                        let previous = self.{{ key }}.insert(id, value);
+                        {% if component.track_changes %}
+                            self.track_insert_{{ key }}(id, previous.is_some());
+                        {% endif %}
+                        {% if component.relation %}
+                            self.relate_{{ key }}(id, previous);
+                        {% endif %}
+                        {% if component.value_index %}
+                            self.reindex_{{ key }}(id, previous.as_ref());
+                        {% endif %}
+                        {% if component.unique %}
+                            self.update_owner(id, ComponentType::{{ component.name }});
+                        {% endif %}
+                        previous.map(ComponentValue::{{ component.name }})
                     }
                 {% else %}
                     ComponentValue::{{ component.name }} => {
-                        if self.{{ key }}.insert(id) {
+                        {% if component.unique %}
+                            if !self.claim(id, ComponentType::{{ component.name }}) {
+                                return None;
+                            }
+                        {% endif %}
+                        {% if component.track_changes %}
+                            let existed = self.{{ key }}.contains(&id);
+                            self.track_insert_{{ key }}(id, existed);
+                        {% endif %}
+                        let inserted = self.{{ key }}.insert(id);
+                        {% if component.unique %}
+                            self.update_owner(id, ComponentType::{{ component.name }});
+                        {% endif %}
+                        if inserted {
                             Some(ComponentValue::{{ component.name }})
                         } else {
                             None
@@ -135,6 +327,16 @@ impl EntityStore {
         }
     }
 
+    {% include "entity_store_tracking" %}
+
+    {% include "entity_store_resources" %}
+
+    {% include "entity_store_relations" %}
+
+    {% include "entity_store_indexes" %}
+
+    {% include "entity_store_unique" %}
+
     pub fn clone_values<A: Append<(EntityId, ComponentValue)>>(&self, buf: &mut A) {
         {% for key, component in components %}
             {% if component.storage %}
@@ -165,9 +367,70 @@ impl EntityStore {
                 {% endif %}
             {% endif %}
         {% endfor %}
+        {% if resources %}
+        self.clone_resource_changes(buf);
+        {% endif %}
     }
 
-    pub fn component_ref_iter(&self, entity_id: EntityId, component_type_iter: ComponentTypeSetIter) -> ComponentRefIter {
+    /// Appends the changes which, when committed to this store,
+    /// make its contents match `other`. Values of components declared
+    /// with `partial_eq` are only inserted if they differ. Values of
+    /// other components are inserted whenever they are present in `other`.
+    pub fn diff_changes<A: Append<EntityChange>>(&self, other: &EntityStore, buf: &mut A) {
+        {% for key, component in components %}
+            {% if component.storage %}
+                {% if component.type %}
+                    for (id, value) in other.{{ key }}.iter() {
+                        let id = id.clone();
+                        {% if component.partial_eq %}
+                            if self.{{ key }}.get(&id) == Some(value) {
+                                continue;
+                            }
+                        {% endif %}
+                        buf.append(insert::{{ key }}(id, value.clone()));
+                    }
+                    for (id, _) in self.{{ key }}.iter() {
+                        let id = id.clone();
+                        if !other.{{ key }}.contains_key(&id) {
+                            buf.append(remove::{{ key }}(id));
+                        }
+                    }
+                {% else %}
+                    for id in other.{{ key }}.iter() {
+                        let id = id.clone();
+                        if !self.{{ key }}.contains(&id) {
+                            buf.append(insert::{{ key }}(id));
+                        }
+                    }
+                    for id in self.{{ key }}.iter() {
+                        let id = id.clone();
+                        if !other.{{ key }}.contains(&id) {
+                            buf.append(remove::{{ key }}(id));
+                        }
+                    }
+                {% endif %}
+            {% endif %}
+        {% endfor %}
+        {% if resources %}
+        self.diff_resource_changes(other, buf);
+        {% endif %}
+    }
+}
+{% endif %}
+
+impl EntityStore {
+    /// Returns the changes which, when committed to this store, make its
+    /// contents match `other`. Only components declared with `partial_eq`
+    /// are compared, so the diff is only minimal if every component with
+    /// a type is declared with `partial_eq`. Values of other components
+    /// present in `other` are always re-inserted, even when unchanged.
+    pub fn diff(&self, other: &EntityStore) -> Vec<EntityChange> {
+        let mut changes = Vec::new();
+        self.diff_changes(other, &mut changes);
+        changes
+    }
+
+    pub fn component_ref_iter(&self, entity_id: EntityId, component_type_iter: ComponentTypeSetIter) -> ComponentRefIter<'_> {
         ComponentRefIter {
             entity_store: self,
             entity_id,
@@ -175,7 +438,7 @@ impl EntityStore {
         }
     }
 
-    pub fn component_drain(&mut self, entity_id: EntityId, component_type_iter: ComponentTypeSetIter) -> ComponentDrain {
+    pub fn component_drain(&mut self, entity_id: EntityId, component_type_iter: ComponentTypeSetIter) -> ComponentDrain<'_> {
         ComponentDrain {
             entity_store: self,
             entity_id,
@@ -184,7 +447,7 @@ impl EntityStore {
     }
 
     pub fn component_drain_insert(&mut self, source_id: EntityId, dest_id: EntityId, component_type_iter: ComponentTypeSetIter)
-        -> ComponentDrainInsert
+        -> ComponentDrainInsert<'_>
     {
         let drain = self.component_drain(source_id, component_type_iter);
         ComponentDrainInsert {
@@ -192,6 +455,20 @@ impl EntityStore {
             dest_id,
         }
     }
+
+    /// Returns changes which insert copies of the source entity's
+    /// components into the destination entity, leaving the source unchanged.
+    {%- if unique_components %}
+    /// Unique components are not copied.
+    {%- endif %}
+    pub fn clone_entity(&self, source_id: EntityId, dest_id: EntityId, component_type_iter: ComponentTypeSetIter)
+        -> CloneEntity<'_>
+    {
+        CloneEntity {
+            refs: self.component_ref_iter(source_id, component_type_iter),
+            dest_id,
+        }
+    }
 }
 
 impl Default for EntityStore {
@@ -209,9 +486,13 @@ pub struct ComponentRefIter<'a> {
 impl<'a> Iterator for ComponentRefIter<'a> {
     type Item = ComponentRef<'a>;
     fn next(&mut self) -> Option<Self::Item> {
-        self.component_type_iter.next().and_then(|component_type| {
-            self.entity_store.get(self.entity_id, component_type)
-        })
+        // skip components which aren't stored
+        for component_type in self.component_type_iter.by_ref() {
+            if let Some(component_ref) = self.entity_store.get(self.entity_id, component_type) {
+                return Some(component_ref);
+            }
+        }
+        None
     }
 }
 
@@ -224,9 +505,13 @@ pub struct ComponentDrain<'a> {
 impl<'a> Iterator for ComponentDrain<'a> {
     type Item = ComponentValue;
     fn next(&mut self) -> Option<Self::Item> {
-        self.component_type_iter.next().and_then(|component_type| {
-            self.entity_store.remove(self.entity_id, component_type)
-        })
+        // skip components which aren't stored
+        for component_type in self.component_type_iter.by_ref() {
+            if let Some(value) = self.entity_store.remove(self.entity_id, component_type) {
+                return Some(value);
+            }
+        }
+        None
     }
 }
 
@@ -243,3 +528,29 @@ impl<'a> Iterator for ComponentDrainInsert<'a> {
         })
     }
 }
+
+pub struct CloneEntity<'a> {
+    refs: ComponentRefIter<'a>,
+    dest_id: EntityId,
+}
+
+impl<'a> Iterator for CloneEntity<'a> {
+    type Item = EntityChange;
+    fn next(&mut self) -> Option<Self::Item> {
+        {% if unique_components %}
+        for component_ref in self.refs.by_ref() {
+            match component_ref.typ() {
+                {% for component in unique_components %}
+                    ComponentType::{{ component.name }} => continue,
+                {% endfor %}
+                _ => return Some(EntityChange::Insert(self.dest_id, component_ref.to_value())),
+            }
+        }
+        None
+        {% else %}
+        self.refs.next().map(|component_ref| {
+            EntityChange::Insert(self.dest_id, component_ref.to_value())
+        })
+        {% endif %}
+    }
+}
@@ -0,0 +1,38 @@
+{% for key, component in components %}
+    {% if component.value_index %}
+    /// Entities whose `{{ key }}` component is equal to `value`.
+    pub fn entities_with_{{ key }}<'a>(&'a self, value: &{{ component.type }}) -> Box<dyn Iterator<Item = EntityId> + 'a> {
+        Box::new(self.index_{{ key }}.get(value).into_iter().flat_map(|ids| ids.iter().cloned()))
+    }
+
+        {% if component.value_index.type == "btree" %}
+    /// Entities whose `{{ key }}` component lies within `range`, in order of value.
+    pub fn entities_with_{{ key }}_in_range<'a, R: RangeBounds<{{ component.type }}>>(&'a self, range: R)
+        -> Box<dyn Iterator<Item = (EntityId, &'a {{ component.type }})> + 'a>
+    {
+        Box::new(self.index_{{ key }}.range(range).flat_map(|(value, ids)| {
+            ids.iter().map(move |&id| (id, value))
+        }))
+    }
+
+        {% endif %}
+    // Moves an entity from the index entry of its previous `{{ key }}`
+    // component to the entry of its current one.
+    fn reindex_{{ key }}(&mut self, id: EntityId, previous: Option<&{{ component.type }}>) {
+        if let Some(previous) = previous {
+            let empty = if let Some(ids) = self.index_{{ key }}.get_mut(previous) {
+                ids.remove(&id);
+                ids.is_empty()
+            } else {
+                false
+            };
+            if empty {
+                self.index_{{ key }}.remove(previous);
+            }
+        }
+        if let Some(current) = self.get_{{ key }}(id).cloned() {
+            self.index_{{ key }}.entry(current).or_default().insert(id);
+        }
+    }
+    {% endif %}
+{% endfor %}
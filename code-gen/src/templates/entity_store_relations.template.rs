@@ -0,0 +1,28 @@
+{% for key, relation in relations %}
+    /// Entities whose `{{ key }}` relation refers to `parent`.
+    pub fn children_{{ key }}(&self, parent: EntityId) -> &[EntityId] {
+        self.children_{{ key }}.get(&parent).map(|children| children.as_slice()).unwrap_or(&[])
+    }
+
+    // Moves an entity from the children of the entity its `{{ key }}`
+    // component previously referred to, to the children of the entity
+    // it currently refers to.
+    fn relate_{{ key }}(&mut self, child: EntityId, previous: Option<EntityId>) {
+        if let Some(parent) = previous {
+            let empty = if let Some(children) = self.children_{{ key }}.get_mut(&parent) {
+                if let Some(index) = children.iter().position(|&c| c == child) {
+                    children.swap_remove(index);
+                }
+                children.is_empty()
+            } else {
+                false
+            };
+            if empty {
+                self.children_{{ key }}.remove(&parent);
+            }
+        }
+        if let Some(&parent) = self.get_{{ key }}(child) {
+            self.children_{{ key }}.entry(parent).or_default().push(child);
+        }
+    }
+{% endfor %}
@@ -0,0 +1,69 @@
+{% if resources %}
+    pub fn resources(&self) -> &Resources {
+        &self.resources
+    }
+
+    {% for key, resource in resources %}
+    pub fn {{ key }}(&self) -> Option<&{{ resource.type }}> {
+        self.resources.{{ key }}.as_ref()
+    }
+
+    pub fn set_{{ key }}(&mut self, value: {{ resource.type }}) -> Option<{{ resource.type }}> {
+        self.resources.{{ key }}.replace(value)
+    }
+
+    pub fn clear_{{ key }}(&mut self) -> Option<{{ resource.type }}> {
+        self.resources.{{ key }}.take()
+    }
+    {% endfor %}
+
+    pub fn get_resource(&self, resource_type: ResourceType) -> Option<ResourceRef<'_>> {
+        match resource_type {
+            {% for key, resource in resources %}
+                ResourceType::{{ resource.name }} => self.resources.{{ key }}.as_ref().map(ResourceRef::{{ resource.name }}),
+            {% endfor %}
+        }
+    }
+
+    pub fn set_resource(&mut self, resource_value: ResourceValue) -> Option<ResourceValue> {
+        match resource_value {
+            {% for key, resource in resources %}
+                ResourceValue::{{ resource.name }}(value) => {
+                    self.resources.{{ key }}.replace(value).map(ResourceValue::{{ resource.name }})
+                }
+            {% endfor %}
+        }
+    }
+
+    pub fn clear_resource(&mut self, resource_type: ResourceType) -> Option<ResourceValue> {
+        match resource_type {
+            {% for key, resource in resources %}
+                ResourceType::{{ resource.name }} => self.resources.{{ key }}.take().map(ResourceValue::{{ resource.name }}),
+            {% endfor %}
+        }
+    }
+
+    fn clone_resource_changes<A: Append<EntityChange>>(&self, buf: &mut A) {
+        {% for key, resource in resources %}
+            if let Some(ref value) = self.resources.{{ key }} {
+                buf.append(set::{{ key }}(value.clone()));
+            }
+        {% endfor %}
+    }
+
+    fn diff_resource_changes<A: Append<EntityChange>>(&self, other: &EntityStore, buf: &mut A) {
+        {% for key, resource in resources %}
+            if let Some(ref value) = other.resources.{{ key }} {
+                {% if resource.partial_eq %}
+                    if self.resources.{{ key }}.as_ref() != Some(value) {
+                        buf.append(set::{{ key }}(value.clone()));
+                    }
+                {% else %}
+                    buf.append(set::{{ key }}(value.clone()));
+                {% endif %}
+            } else if self.resources.{{ key }}.is_some() {
+                buf.append(clear::{{ key }}());
+            }
+        {% endfor %}
+    }
+{% endif %}
@@ -0,0 +1,63 @@
+    {% for key, component in components %}
+        {% if component.track_changes %}
+    fn track_insert_{{ key }}(&mut self, id: EntityId, existed: bool) {
+        if existed {
+            if !self.added_{{ key }}.contains(&id) {
+                self.changed_{{ key }}.insert(id);
+            }
+        } else if self.removed_{{ key }}.remove(&id) {
+            // removed and re-inserted since changes were last cleared
+            self.changed_{{ key }}.insert(id);
+        } else {
+            self.added_{{ key }}.insert(id);
+        }
+    }
+
+    fn track_remove_{{ key }}(&mut self, id: EntityId, existed: bool) {
+        if existed {
+            self.changed_{{ key }}.remove(&id);
+            if !self.added_{{ key }}.remove(&id) {
+                self.removed_{{ key }}.insert(id);
+            }
+        }
+    }
+
+    /// Entities which gained a `{{ key }}` component since changes were last cleared.
+    pub fn added_{{ key }}(&self) -> &{{ component.storage.set_type }} {
+        &self.added_{{ key }}
+    }
+
+    /// Entities whose `{{ key }}` component was replaced since changes were last cleared.
+    pub fn changed_{{ key }}(&self) -> &{{ component.storage.set_type }} {
+        &self.changed_{{ key }}
+    }
+
+    /// Entities which lost their `{{ key }}` component since changes were last cleared.
+    pub fn removed_{{ key }}(&self) -> &{{ component.storage.set_type }} {
+        &self.removed_{{ key }}
+    }
+
+    pub fn drain_added_{{ key }}(&mut self) -> {{ component.storage.set_type }} {
+        ::std::mem::take(&mut self.added_{{ key }})
+    }
+
+    pub fn drain_changed_{{ key }}(&mut self) -> {{ component.storage.set_type }} {
+        ::std::mem::take(&mut self.changed_{{ key }})
+    }
+
+    pub fn drain_removed_{{ key }}(&mut self) -> {{ component.storage.set_type }} {
+        ::std::mem::take(&mut self.removed_{{ key }})
+    }
+        {% endif %}
+    {% endfor %}
+
+    /// Forgets which components have been added, changed or removed.
+    pub fn clear_changes(&mut self) {
+        {% for key, component in components %}
+            {% if component.track_changes %}
+                self.added_{{ key }}.clear();
+                self.changed_{{ key }}.clear();
+                self.removed_{{ key }}.clear();
+            {% endif %}
+        {% endfor %}
+    }
@@ -0,0 +1,101 @@
+{% if unique_components %}
+    {% for component in unique_components %}
+    /// The entity which has the unique `{{ component.key }}` component.
+    pub fn get_{{ component.key }}_entity(&self) -> Option<EntityId> {
+        self.owner_{{ component.key }}
+    }
+
+    {% endfor %}
+    /// The entity which has the given component, if it's unique.
+    pub fn unique_owner(&self, component_type: ComponentType) -> Option<EntityId> {
+        match component_type {
+            {% for component in unique_components %}
+                ComponentType::{{ component.name }} => self.owner_{{ component.key }},
+            {% endfor %}
+            _ => None,
+        }
+    }
+
+    // Returns the entity which has the unique component being given
+    // to a different entity by a change.
+    fn unique_conflict(&self, change: &EntityChange) -> Option<UniqueViolation> {
+        if let &EntityChange::Insert(id, ref value) = change {
+            let component_type = value.typ();
+            if let Some(owner) = self.unique_owner(component_type) {
+                if owner != id && self.contains(owner, component_type) {
+                    return Some(UniqueViolation { component_type, owner, id });
+                }
+            }
+        }
+        None
+    }
+
+    /// Returns the change which removes a unique component from the
+    /// entity which has it, if committing the given change would give
+    /// that component to a different entity. Such components are removed
+    /// from their previous owner when the change is committed.
+    pub fn implied_removal(&self, change: &EntityChange) -> Option<EntityChange> {
+        self.unique_conflict(change).and_then(|conflict| {
+            match conflict.component_type {
+                {% for component in unique_components %}
+                    {% if component.unique == "replace" %}
+                        ComponentType::{{ component.name }} => Some(EntityChange::Remove(conflict.owner, conflict.component_type)),
+                    {% endif %}
+                {% endfor %}
+                _ => None,
+            }
+        })
+    }
+
+    /// Checks that committing a change won't give a unique component
+    /// declared with `on_conflict = "error"` to a second entity.
+    /// Committing such a change leaves the store unchanged.
+    pub fn check_unique(&self, change: &EntityChange) -> Result<(), UniqueViolation> {
+        match self.unique_conflict(change) {
+            {% for component in unique_components %}
+                {% if component.unique == "error" %}
+                    Some(conflict) if conflict.component_type == ComponentType::{{ component.name }} => Err(conflict),
+                {% endif %}
+            {% endfor %}
+            _ => Ok(()),
+        }
+    }
+
+    // Called before a component is given to an entity. Returns false
+    // if the component can't be given to the entity as another entity
+    // already has it.
+    fn claim(&mut self, id: EntityId, component_type: ComponentType) -> bool {
+        let owner = match self.unique_owner(component_type) {
+            Some(owner) if owner != id && self.contains(owner, component_type) => owner,
+            _ => return true,
+        };
+        match component_type {
+            {% for component in unique_components %}
+                {% if component.unique == "error" %}
+                    ComponentType::{{ component.name }} => false,
+                {% endif %}
+            {% endfor %}
+            _ => {
+                self.remove(owner, component_type);
+                true
+            }
+        }
+    }
+
+    // Called after a component is inserted or removed.
+    fn update_owner(&mut self, id: EntityId, component_type: ComponentType) {
+        let has_component = self.contains(id, component_type);
+        match component_type {
+            {% for component in unique_components %}
+                ComponentType::{{ component.name }} => {
+                    if has_component {
+                        self.owner_{{ component.key }} = Some(id);
+                    } else if self.owner_{{ component.key }} == Some(id) {
+                        self.owner_{{ component.key }} = None;
+                    }
+                }
+            {% endfor %}
+            _ => {}
+        }
+    }
+{% endif %}
@@ -1,53 +1,95 @@
 #![allow(dead_code)]
 
-use std::mem;
 use std::slice;
 use std::iter;
-use super::EntityId;
+use super::{EntityId, entity_id_index};
+{% if generational_ids %}
+use super::entity_id_is_stale;
+{% endif %}
+{% if generational_ids %}
+use super::{EntityIdIndex, EntityIdGeneration};
+{% endif %}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EntityVecMap<T> {
     components: Vec<Option<T>>,
+    {% if generational_ids %}
+    generations: Vec<EntityIdGeneration>,
+    {% endif %}
 }
 
 impl<T> EntityVecMap<T> {
     pub fn new() -> Self {
         EntityVecMap {
             components: Vec::new(),
+            {% if generational_ids %}
+            generations: Vec::new(),
+            {% endif %}
         }
     }
 
+    {% if generational_ids %}
+    fn is_current(&self, id: &EntityId) -> bool {
+        self.generations.get(entity_id_index(*id)) == Some(&id.generation())
+    }
+    {% endif %}
+
     pub fn remove(&mut self, id: &EntityId) -> Option<T> {
-        if (*id as usize) >= self.components.len() {
+        if entity_id_index(*id) >= self.components.len() {
+            return None;
+        }
+        {% if generational_ids %}
+        if !self.is_current(id) {
             return None;
         }
+        {% endif %}
 
-        mem::replace(&mut self.components[*id as usize], None)
+        self.components[entity_id_index(*id)].take()
     }
 
     pub fn get(&self, id: &EntityId) -> Option<&T> {
-        self.components.get(*id as usize).and_then(Option::as_ref)
+        {% if generational_ids %}
+        if !self.is_current(id) {
+            return None;
+        }
+        {% endif %}
+        self.components.get(entity_id_index(*id)).and_then(Option::as_ref)
     }
 
     pub fn get_mut(&mut self, id: &EntityId) -> Option<&mut T> {
-        self.components.get_mut(*id as usize).and_then(Option::as_mut)
+        {% if generational_ids %}
+        if !self.is_current(id) {
+            return None;
+        }
+        {% endif %}
+        self.components.get_mut(entity_id_index(*id)).and_then(Option::as_mut)
     }
 
     pub fn clear(&mut self) {
         self.components.clear();
+        {% if generational_ids %}
+        self.generations.clear();
+        {% endif %}
     }
 
     pub fn contains_key(&self, id: &EntityId) -> bool {
         self.get(id).is_some()
     }
 
-    pub fn iter(&self) -> EntityVecMapIter<T> {
+    pub fn len_upper_bound(&self) -> usize {
+        self.components.len()
+    }
+
+    pub fn iter(&self) -> EntityVecMapIter<'_, T> {
         EntityVecMapIter {
             iter: self.components.iter().enumerate(),
+            {% if generational_ids %}
+            generations: &self.generations,
+            {% endif %}
         }
     }
 
-    pub fn entry(&mut self, id: &EntityId) -> EntityVecMapEntry<T> {
+    pub fn entry(&mut self, id: &EntityId) -> EntityVecMapEntry<'_, T> {
         if self.contains_key(id) {
             let value = self.get_mut(id).unwrap();
             EntityVecMapEntry::Occupied(value)
@@ -62,13 +104,33 @@ impl<T> EntityVecMap<T> {
 
 impl<T: Clone> EntityVecMap<T> {
     pub fn insert(&mut self, id: EntityId, component: T) -> Option<T> {
+        let index = entity_id_index(id);
+
+        {% if generational_ids %}
+        if let Some(generation) = self.generations.get_mut(index) {
+            if *generation != id.generation() {
+                if entity_id_is_stale(id, EntityId::new(id.index(), *generation)) {
+                    // the id belongs to an entity which has since been freed
+                    return None;
+                }
+                // any value in this slot belongs to a freed entity
+                *generation = id.generation();
+                self.components[index] = Some(component);
+                return None;
+            }
+        }
+        {% endif %}
 
-        if let Some(value) = self.components.get_mut(id as usize) {
-            return mem::replace(value, Some(component));
+        if let Some(value) = self.components.get_mut(index) {
+            return value.replace(component);
         }
 
-        self.components.resize(id as usize, None);
+        self.components.resize(index, None);
         self.components.push(Some(component));
+        {% if generational_ids %}
+        self.generations.resize(index, 0);
+        self.generations.push(id.generation());
+        {% endif %}
 
         None
     }
@@ -82,14 +144,22 @@ impl<T> Default for EntityVecMap<T> {
 
 pub struct EntityVecMapIter<'a, T: 'a> {
     iter: iter::Enumerate<slice::Iter<'a, Option<T>>>,
+    {% if generational_ids %}
+    generations: &'a [EntityIdGeneration],
+    {% endif %}
 }
 
 impl<'a, T: 'a> Iterator for EntityVecMapIter<'a, T> {
     type Item = (EntityId, &'a T);
     fn next(&mut self) -> Option<Self::Item> {
-        while let Some((id, maybe_value)) = self.iter.next() {
+        for (id, maybe_value) in self.iter.by_ref() {
             if let Some(value) = maybe_value.as_ref() {
-                return Some((id as EntityId, value));
+                {% if generational_ids %}
+                let id = EntityId::new(id as EntityIdIndex, self.generations[id]);
+                {% else %}
+                let id = id as EntityId;
+                {% endif %}
+                return Some((id, value));
             }
         }
 
@@ -111,7 +181,7 @@ impl<'a, T: Clone> EntityVecMapEntry<'a, T> {
             EntityVecMapEntry::Occupied(v) => v,
             EntityVecMapEntry::Vacant { map, id } => {
                 map.insert(id, default);
-                map.components[id as usize].as_mut().unwrap()
+                map.get_mut(&id).expect("Failed to insert into EntityVecMap with out of date id")
             }
         }
     }
@@ -120,7 +190,7 @@ impl<'a, T: Clone> EntityVecMapEntry<'a, T> {
             EntityVecMapEntry::Occupied(v) => v,
             EntityVecMapEntry::Vacant { map, id } => {
                 map.insert(id, default());
-                map.components[id as usize].as_mut().unwrap()
+                map.get_mut(&id).expect("Failed to insert into EntityVecMap with out of date id")
             }
         }
 
@@ -131,24 +201,63 @@ impl<'a, T: Clone> EntityVecMapEntry<'a, T> {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EntityVecSet {
     entities: Vec<u64>,
+    {% if generational_ids %}
+    generations: Vec<EntityIdGeneration>,
+    {% endif %}
 }
 
 impl EntityVecSet {
     pub fn new() -> Self {
         EntityVecSet {
             entities: Vec::new(),
+            {% if generational_ids %}
+            generations: Vec::new(),
+            {% endif %}
         }
     }
 
     fn index_mask(id: EntityId) -> (usize, u64) {
-        let index = (id / 64) as usize;
-        let offset = (id % 64) as u32;
-        let mask = (1 as u64) << offset;
+        let index = entity_id_index(id) / 64;
+        let offset = (entity_id_index(id) % 64) as u32;
+        let mask = 1u64 << offset;
 
         (index, mask)
     }
 
+    {% if generational_ids %}
+    fn is_current(&self, id: &EntityId) -> bool {
+        self.generations.get(entity_id_index(*id)) == Some(&id.generation())
+    }
+
+    // Records the generation of an id about to be inserted, clearing
+    // the slot if it was occupied by an entity which has since been
+    // freed. Returns false if the id itself has since been freed.
+    fn update_generation(&mut self, id: EntityId) -> bool {
+        let index = entity_id_index(id);
+        if index >= self.generations.len() {
+            self.generations.resize(index + 1, 0);
+        }
+
+        if self.generations[index] != id.generation() {
+            if entity_id_is_stale(id, EntityId::new(id.index(), self.generations[index])) {
+                return false;
+            }
+            self.generations[index] = id.generation();
+            let (index, mask) = Self::index_mask(id);
+            if let Some(bits) = self.entities.get_mut(index) {
+                *bits &= !mask;
+            }
+        }
+        true
+    }
+    {% endif %}
+
     pub fn insert(&mut self, id: EntityId) -> bool {
+        {% if generational_ids %}
+        if !self.update_generation(id) {
+            return false;
+        }
+        {% endif %}
         let (index, mask) = Self::index_mask(id);
 
         if let Some(bits) = self.entities.get_mut(index) {
@@ -164,6 +273,11 @@ impl EntityVecSet {
     }
 
     pub fn remove(&mut self, id: &EntityId) -> bool {
+        {% if generational_ids %}
+        if !self.is_current(id) {
+            return false;
+        }
+        {% endif %}
         let (index, mask) = Self::index_mask(*id);
 
         if let Some(bits) = self.entities.get_mut(index) {
@@ -176,6 +290,11 @@ impl EntityVecSet {
     }
 
     pub fn contains(&self, id: &EntityId) -> bool {
+        {% if generational_ids %}
+        if !self.is_current(id) {
+            return false;
+        }
+        {% endif %}
         let (index, mask) = Self::index_mask(*id);
 
         if let Some(bits) = self.entities.get(index) {
@@ -187,6 +306,13 @@ impl EntityVecSet {
 
     pub fn clear(&mut self) {
         self.entities.clear();
+        {% if generational_ids %}
+        self.generations.clear();
+        {% endif %}
+    }
+
+    pub fn len_upper_bound(&self) -> usize {
+        self.entities.len() * 64
     }
 
     pub fn is_empty(&self) -> bool {
@@ -198,12 +324,15 @@ impl EntityVecSet {
         true
     }
 
-    pub fn iter(&self) -> EntityVecSetIter {
+    pub fn iter(&self) -> EntityVecSetIter<'_> {
         let mut iter = self.entities.iter();
         EntityVecSetIter {
-            current: iter.next().map(Clone::clone).unwrap_or(0),
+            current: iter.next().copied().unwrap_or(0),
             iter,
             base: 0,
+            {% if generational_ids %}
+            generations: &self.generations,
+            {% endif %}
         }
     }
 }
@@ -217,7 +346,10 @@ impl Default for EntityVecSet {
 pub struct EntityVecSetIter<'a> {
     iter: slice::Iter<'a, u64>,
     current: u64,
-    base: EntityId,
+    base: usize,
+    {% if generational_ids %}
+    generations: &'a [EntityIdGeneration],
+    {% endif %}
 }
 
 impl<'a> Iterator for EntityVecSetIter<'a> {
@@ -235,6 +367,11 @@ impl<'a> Iterator for EntityVecSetIter<'a> {
         let trailing = self.current.trailing_zeros();
         self.current &= !(1 << trailing);
 
-        Some(self.base + trailing as EntityId)
+        let index = self.base + trailing as usize;
+        {% if generational_ids %}
+        Some(EntityId::new(index as EntityIdIndex, self.generations[index]))
+        {% else %}
+        Some(index as EntityId)
+        {% endif %}
     }
 }
@@ -0,0 +1,437 @@
+#![allow(dead_code)]
+#![allow(unused_imports)]
+
+use std::collections::HashMap;
+use std::slice;
+use super::{EntityId, EntityChange, EntityStore, EntityComponentTable, EntityIdAllocator,
+            EntityHooks, CommitError, ComponentType, ComponentTypeSet, ComponentValue};
+{% if unique_components %}
+use super::UniqueViolation;
+{% endif %}
+{% if spatial_hashes %}
+use super::{Size, EntityVecSet};
+{% for spatial_hash in spatial_hashes %}
+use super::{{ spatial_hash.name }}SpatialHashTable;
+{% endfor %}
+{% endif %}
+
+/// Owns an `EntityStore` along with the data structures derived
+/// from it, and keeps them in sync by applying each change to
+/// everything in the correct order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntityWorld {
+    entity_store: EntityStore,
+    entity_component_table: EntityComponentTable,
+    id_allocator: EntityIdAllocator,
+    {% for spatial_hash in spatial_hashes %}
+    {{ spatial_hash.key }}: {{ spatial_hash.name }}SpatialHashTable,
+    {% endfor %}
+    {% if spatial_hashes %}
+    time: u64,
+    {% endif %}
+}
+
+impl EntityWorld {
+    {% if spatial_hashes %}
+    pub fn new({% for spatial_hash in spatial_hashes %}{{ spatial_hash.size }}: Size, {% endfor %}) -> Self {
+        Self {
+            entity_store: EntityStore::new(),
+            entity_component_table: EntityComponentTable::new(),
+            id_allocator: EntityIdAllocator::new(),
+            {% for spatial_hash in spatial_hashes %}
+            {{ spatial_hash.key }}: {{ spatial_hash.name }}SpatialHashTable::new({{ spatial_hash.size }}),
+            {% endfor %}
+            time: 0,
+        }
+    }
+    {% else %}
+    pub fn new() -> Self {
+        Self {
+            entity_store: EntityStore::new(),
+            entity_component_table: EntityComponentTable::new(),
+            id_allocator: EntityIdAllocator::new(),
+        }
+    }
+    {% endif %}
+
+    pub fn entity_store(&self) -> &EntityStore {
+        &self.entity_store
+    }
+
+    pub fn entity_component_table(&self) -> &EntityComponentTable {
+        &self.entity_component_table
+    }
+
+    {% for spatial_hash in spatial_hashes %}
+    pub fn {{ spatial_hash.key }}(&self) -> &{{ spatial_hash.name }}SpatialHashTable {
+        &self.{{ spatial_hash.key }}
+    }
+    {% endfor %}
+
+    {% if spatial_hashes %}
+    /// The time passed to the spatial hash when changes are committed.
+    pub fn time(&self) -> u64 {
+        self.time
+    }
+
+    pub fn set_time(&mut self, time: u64) {
+        self.time = time;
+    }
+    {% endif %}
+
+    // Returns true if a change is to an id which isn't currently allocated
+    // by this world. Such changes are ignored, as the id's slot may have
+    // been reused by another entity whose components they would overwrite.
+    fn is_unallocated(&self, change: &EntityChange) -> bool {
+        match change {
+            &EntityChange::Insert(id, _) | &EntityChange::Remove(id, _) => !self.id_allocator.is_allocated(id),
+            {% if resources %}
+            _ => false,
+            {% endif %}
+        }
+    }
+
+    /// Applies a change to the store and everything derived from it.
+    /// Changes to ids which aren't currently allocated by this world,
+    /// such as ids of despawned entities, are ignored.
+    {%- if unique_errors %}
+    /// Changes which would give a unique component declared with
+    /// `on_conflict = "error"` to a second entity are rejected,
+    /// leaving the world unchanged.
+    {%- endif %}
+    pub fn commit(&mut self, change: EntityChange){% if unique_errors %} -> Result<(), UniqueViolation>{% endif %} {
+        if self.is_unallocated(&change) {
+            return{% if unique_errors %} Ok(()){% endif %};
+        }
+        {% if unique_errors %}
+        self.entity_store.check_unique(&change)?;
+        self.apply(change);
+        Ok(())
+        {% else %}
+        self.apply(change);
+        {% endif %}
+    }
+
+    // Applies a change which is known to be to an allocated id and
+    // not to violate a unique component.
+    fn apply(&mut self, change: EntityChange) {
+        {% if unique_components %}
+        // the removal of a unique component from its previous owner
+        // must reach the spatial hash and component table too
+        if let Some(removal) = self.entity_store.implied_removal(&change) {
+            self.apply(removal);
+        }
+        {% endif %}
+        {% if spatial_hashes %}
+        // the spatial hash reads the value being replaced from the store
+        // so must be updated before the change is committed
+        {% endif %}
+        {% for spatial_hash in spatial_hashes %}
+        self.{{ spatial_hash.key }}.update(&self.entity_store, &change, self.time);
+        {% endfor %}
+        self.entity_component_table.update(&change);
+        self.entity_store.apply(change);
+    }
+
+    /// Checks whether committing a change would be ignored or violate a
+    /// constraint. In addition to the checks made by the store, this checks
+    /// that the entity's id is currently allocated by this world
+    {%- if spatial_hashes %} and that positions are inside each spatial hash{% endif %}.
+    {%- if constraints %}
+    /// Changes which would leave the entity violating a constraint
+    /// from the spec are also rejected.
+    {%- endif %}
+    pub fn check_commit(&self, change: &EntityChange) -> Result<(), CommitError> {
+        self.check_commit_batch(slice::from_ref(change))
+    }
+
+    /// Checks whether committing a sequence of changes would be ignored or
+    /// violate a constraint. Each change is checked as by `check_commit`,
+    /// against the state left by the changes before it.
+    {%- if constraints %}
+    /// Constraints are only checked once all the changes have been applied,
+    /// so an entity may violate them part way through the sequence.
+    {%- endif %}
+    pub fn check_commit_batch(&self, changes: &[EntityChange]) -> Result<(), CommitError> {
+        // component types of entities changed by the changes checked so far
+        let mut changed = HashMap::new();
+        {% if resources %}
+        // whether each resource changed by the changes checked so far is set
+        let mut resources = HashMap::new();
+        {% endif %}
+        for change in changes {
+            match change {
+                &EntityChange::Insert(id, ref value) => {
+                    if !self.id_allocator.is_allocated(id) {
+                        return Err(CommitError::UnallocatedId(id));
+                    }
+                    let component_type = value.typ();
+                    if !component_type.is_stored() {
+                        return Err(CommitError::NotStored(id, component_type));
+                    }
+                    {% for spatial_hash in spatial_hashes %}
+                    if let &ComponentValue::{{ spatial_hash.position_component.name }}(position) = value {
+                        if self.{{ spatial_hash.key }}.get(position).is_none() {
+                            return Err(CommitError::PositionOutsideGrid(id, value.clone()));
+                        }
+                    }
+                    {% endfor %}
+                    {% if unique_components %}
+                    if component_type.is_unique() {
+                        let owner = self.entity_store.unique_owner(component_type).into_iter()
+                            .chain(changed.keys().cloned())
+                            .find(|&owner| {
+                                owner != id && self.component_types_after(&changed, owner).contains(component_type)
+                            });
+                        if let Some(owner) = owner {
+                            match component_type {
+                                {% for component in unique_components %}
+                                    {% if component.unique == "error" %}
+                                        ComponentType::{{ component.name }} => {
+                                            let violation = UniqueViolation { component_type, owner, id };
+                                            return Err(CommitError::UniqueViolation(violation));
+                                        }
+                                    {% endif %}
+                                {% endfor %}
+                                _ => {
+                                    // the component is removed from its previous owner
+                                    let mut component_types = self.component_types_after(&changed, owner);
+                                    component_types.remove(component_type);
+                                    changed.insert(owner, component_types);
+                                }
+                            }
+                        }
+                    }
+                    {% endif %}
+                    let mut component_types = self.component_types_after(&changed, id);
+                    component_types.insert(component_type);
+                    changed.insert(id, component_types);
+                }
+                &EntityChange::Remove(id, component_type) => {
+                    if !self.id_allocator.is_allocated(id) {
+                        return Err(CommitError::UnallocatedId(id));
+                    }
+                    let mut component_types = self.component_types_after(&changed, id);
+                    if !component_types.contains(component_type) {
+                        return Err(CommitError::AbsentComponent(id, component_type));
+                    }
+                    component_types.remove(component_type);
+                    changed.insert(id, component_types);
+                }
+                {% if resources %}
+                &EntityChange::SetResource(ref value) => {
+                    resources.insert(value.typ(), true);
+                }
+                &EntityChange::ClearResource(resource_type) => {
+                    let is_set = resources.get(&resource_type).cloned()
+                        .unwrap_or_else(|| self.entity_store.get_resource(resource_type).is_some());
+                    if !is_set {
+                        return Err(CommitError::AbsentResource(resource_type));
+                    }
+                    resources.insert(resource_type, false);
+                }
+                {% endif %}
+            }
+        }
+        {% if constraints %}
+        for (&id, component_types) in changed.iter() {
+            component_types.check_constraints(id).map_err(CommitError::ConstraintViolation)?;
+        }
+        {% endif %}
+        Ok(())
+    }
+
+    // Returns the component types of an entity after the changes
+    // which have been checked so far by `check_commit_batch`.
+    fn component_types_after(&self, changed: &HashMap<EntityId, ComponentTypeSet>, id: EntityId) -> ComponentTypeSet {
+        changed.get(&id).cloned().unwrap_or_else(|| self.entity_component_table.get(id))
+    }
+
+    /// Commits a change if `check_commit` succeeds. Otherwise the
+    /// world is left unchanged.
+    pub fn try_commit(&mut self, change: EntityChange) -> Result<(), CommitError> {
+        self.check_commit(&change)?;
+        self.apply(change);
+        Ok(())
+    }
+
+    /// Commits a sequence of changes with `commit_batch` if
+    /// `check_commit_batch` succeeds. Otherwise the world is
+    /// left unchanged.
+    pub fn try_commit_batch<I: IntoIterator<Item = EntityChange>>(&mut self, changes: I) -> Result<(), CommitError> {
+        let changes = changes.into_iter().collect::<Vec<_>>();
+        self.check_commit_batch(&changes)?;
+        {% if unique_errors %}
+        self.commit_batch(changes).map_err(CommitError::UniqueViolation)
+        {% else %}
+        self.commit_batch(changes);
+        Ok(())
+        {% endif %}
+    }
+
+    /// Commits a change as with `commit`, invoking any relevant hooks
+    /// once the change has been applied.
+    pub fn commit_with_hooks(&mut self, change: EntityChange, hooks: &mut EntityHooks){% if unique_errors %} -> Result<(), UniqueViolation>{% endif %} {
+        if self.is_unallocated(&change) {
+            return{% if unique_errors %} Ok(()){% endif %};
+        }
+        {% if unique_errors %}
+        self.entity_store.check_unique(&change)?;
+        self.apply_with_hooks(change, hooks);
+        Ok(())
+        {% else %}
+        self.apply_with_hooks(change, hooks);
+        {% endif %}
+    }
+
+    fn apply_with_hooks(&mut self, change: EntityChange, hooks: &mut EntityHooks) {
+        {% if unique_components %}
+        if let Some(removal) = self.entity_store.implied_removal(&change) {
+            self.apply_with_hooks(removal, hooks);
+        }
+        {% endif %}
+        {% for spatial_hash in spatial_hashes %}
+        self.{{ spatial_hash.key }}.update(&self.entity_store, &change, self.time);
+        {% endfor %}
+        self.entity_component_table.update(&change);
+        self.entity_store.apply_with_hooks(change, hooks);
+    }
+
+    /// Commits a sequence of changes. Rather than updating the spatial
+    /// hash after each change, entities affected by any of the changes
+    /// are removed from the spatial hash before the changes are applied
+    /// and reinserted afterwards. As with `commit`, changes to ids which
+    /// aren't currently allocated are ignored.
+    {%- if unique_errors %}
+    /// Changes which would give a unique component declared with
+    /// `on_conflict = "error"` to a second entity are skipped, and
+    /// the first such violation is returned once the remaining
+    /// changes have been applied.
+    {%- endif %}
+    pub fn commit_batch<I: IntoIterator<Item = EntityChange>>(&mut self, changes: I){% if unique_errors %} -> Result<(), UniqueViolation>{% endif %} {
+        {% if spatial_hashes %}
+        let changes = changes.into_iter().collect::<Vec<_>>();
+        {% for spatial_hash in spatial_hashes %}
+        let mut seen_{{ spatial_hash.key }} = EntityVecSet::new();
+        let mut ids_{{ spatial_hash.key }} = Vec::new();
+        {% endfor %}
+        for change in changes.iter() {
+            if self.is_unallocated(change) {
+                continue;
+            }
+            let (id, typ) = match change {
+                &EntityChange::Insert(id, ref value) => (id, value.typ()),
+                &EntityChange::Remove(id, typ) => (id, typ),
+                {% if resources %}
+                _ => continue,
+                {% endif %}
+            };
+            {% if unique_components %}
+            // a unique component may be removed from its current owner
+            let owner = self.entity_store.unique_owner(typ);
+            {% endif %}
+            {% for spatial_hash in spatial_hashes %}
+            {% if unique_components %}
+            if {{ spatial_hash.name }}SpatialHashTable::affected_by(typ) {
+                if !seen_{{ spatial_hash.key }}.insert(id) {
+                    ids_{{ spatial_hash.key }}.push(id);
+                }
+                if let Some(owner) = owner {
+                    if !seen_{{ spatial_hash.key }}.insert(owner) {
+                        ids_{{ spatial_hash.key }}.push(owner);
+                    }
+                }
+            }
+            {% else %}
+            if {{ spatial_hash.name }}SpatialHashTable::affected_by(typ) && !seen_{{ spatial_hash.key }}.insert(id) {
+                ids_{{ spatial_hash.key }}.push(id);
+            }
+            {% endif %}
+            {% endfor %}
+        }
+        {% for spatial_hash in spatial_hashes %}
+        self.{{ spatial_hash.key }}.remove_entities(&self.entity_store, ids_{{ spatial_hash.key }}.iter().cloned(), self.time);
+        {% endfor %}
+        {% endif %}
+        {% if unique_errors %}
+        let mut violation = None;
+        {% endif %}
+        for change in changes {
+            if self.is_unallocated(&change) {
+                continue;
+            }
+            {% if unique_errors %}
+            if let Err(error) = self.entity_store.check_unique(&change) {
+                violation = violation.or(Some(error));
+                continue;
+            }
+            {% endif %}
+            {% if unique_components %}
+            if let Some(removal) = self.entity_store.implied_removal(&change) {
+                self.entity_component_table.update(&removal);
+                self.entity_store.apply(removal);
+            }
+            {% endif %}
+            self.entity_component_table.update(&change);
+            self.entity_store.apply(change);
+        }
+        {% for spatial_hash in spatial_hashes %}
+        self.{{ spatial_hash.key }}.insert_entities(&self.entity_store, ids_{{ spatial_hash.key }}, self.time);
+        {% endfor %}
+        {% if unique_errors %}
+        match violation {
+            Some(violation) => Err(violation),
+            None => Ok(()),
+        }
+        {% endif %}
+    }
+
+    pub fn spawn(&mut self) -> EntityId {
+        self.id_allocator.allocate()
+    }
+
+    /// Allocates a new entity with copies of the components of an
+    /// existing entity, returning the new entity's id.
+    pub fn clone_entity(&mut self, source_id: EntityId) -> EntityId {
+        let id = self.spawn();
+        let changes: Vec<_> = self.entity_component_table.clone_entity(source_id, id, &self.entity_store).collect();
+        // unique components aren't cloned so can't conflict
+        for change in changes {
+            self.apply(change);
+        }
+        id
+    }
+
+    /// Removes all components of an entity and frees its id. Ids which
+    /// aren't currently allocated, such as ids of entities which have
+    /// already been despawned, are ignored.
+    pub fn despawn(&mut self, id: EntityId) {
+        if !self.id_allocator.is_allocated(id) {
+            return;
+        }
+        {% if relations %}
+        let changes = self.entity_component_table.remove_entity_cascading(id, &self.entity_store);
+        // entities removed by a cascading relation are despawned too
+        let cascaded_ids = changes.cascaded_ids().to_vec();
+        {% else %}
+        let changes = self.entity_component_table.remove_entity(id);
+        {% endif %}
+        for change in changes {
+            self.apply(change);
+        }
+        self.id_allocator.free(id);
+        {% if relations %}
+        for id in cascaded_ids {
+            self.id_allocator.free(id);
+        }
+        {% endif %}
+    }
+}
+
+{% if not spatial_hashes %}
+impl Default for EntityWorld {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+{% endif %}
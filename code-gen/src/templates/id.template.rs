@@ -1 +1,26 @@
+{% if generational_ids %}
+pub type EntityId = ::entity_store_helper::GenerationalId<{{ id_type }}>;
+pub type EntityIdIndex = {{ id_type }};
+pub type EntityIdGeneration = {{ id_type }};
+
+pub(super) fn entity_id_index(id: EntityId) -> usize {
+    id.index() as usize
+}
+
+// Returns true if the id refers to an entity which was freed before
+// `occupant`, an id with the same index, was allocated.
+pub(super) fn entity_id_is_stale(id: EntityId, occupant: EntityId) -> bool {
+    id.is_older_than(occupant)
+}
+{% else %}
 pub type EntityId = {{ id_type }};
+
+pub(super) fn entity_id_index(id: EntityId) -> usize {
+    id as usize
+}
+
+// Ids are never reused without generations, so none are stale.
+pub(super) fn entity_id_is_stale(_id: EntityId, _occupant: EntityId) -> bool {
+    false
+}
+{% endif %}
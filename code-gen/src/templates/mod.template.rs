@@ -1,11 +1,30 @@
 pub use self::component::*;
 pub use self::id::*;
 pub use self::entity_vec::*;
+pub use self::entity_sparse_set::*;
 pub use self::entity_store::*;
 pub use self::entity_change::*;
 pub use self::component_type_set::*;
 pub use self::entity_component_table::*;
 pub use self::entity_id_allocator::*;
-{% if spatial_hash %}
+pub use self::modify::*;
+pub use self::entity_world::*;
+pub use self::commit_error::*;
+pub use self::entity_builder::*;
+pub use self::change_journal::*;
+pub use self::entity_hooks::*;
+{% if prefabs %}
+pub use self::prefab::*;
+{% endif %}
+{% if resources %}
+pub use self::resource::*;
+{% endif %}
+{% if layout == "archetype" %}
+pub use self::archetype::*;
+{% endif %}
+{% if queries %}
+pub use self::query::*;
+{% endif %}
+{% if spatial_hashes %}
 pub use self::spatial_hash::*;
 {% endif %}
@@ -0,0 +1,70 @@
+#![allow(dead_code)]
+#![allow(unused_imports)]
+
+use std::ops::{Deref, DerefMut};
+use super::{EntityId, EntityChange, EntityStore, insert};
+use entity_store_helper::append::Append;
+
+/// Mutable access to a copy of a component's value.
+/// When the guard is dropped, if the value was mutably accessed,
+/// an `EntityChange::Insert` of the new value is appended to the
+/// buffer the guard was created with. Committing the buffered
+/// changes keeps any derived data structures (e.g. spatial hash)
+/// consistent with the store.
+pub struct ComponentGuard<'a, T, A: 'a + Append<EntityChange>> {
+    id: EntityId,
+    value: Option<T>,
+    changed: bool,
+    insert: fn(EntityId, T) -> EntityChange,
+    buf: &'a mut A,
+}
+
+impl<'a, T, A: Append<EntityChange>> ComponentGuard<'a, T, A> {
+    pub fn id(&self) -> EntityId {
+        self.id
+    }
+}
+
+impl<'a, T, A: Append<EntityChange>> Deref for ComponentGuard<'a, T, A> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        self.value.as_ref().unwrap()
+    }
+}
+
+impl<'a, T, A: Append<EntityChange>> DerefMut for ComponentGuard<'a, T, A> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.changed = true;
+        self.value.as_mut().unwrap()
+    }
+}
+
+impl<'a, T, A: Append<EntityChange>> Drop for ComponentGuard<'a, T, A> {
+    fn drop(&mut self) {
+        if self.changed {
+            if let Some(value) = self.value.take() {
+                self.buf.append((self.insert)(self.id, value));
+            }
+        }
+    }
+}
+
+impl EntityStore {
+    {% for key, component in components %}
+        {% if component.type and component.storage %}
+    pub fn modify_{{ key }}<'a, A: Append<EntityChange>>(&self, id: EntityId, buf: &'a mut A)
+        -> Option<ComponentGuard<'a, {{ component.type }}, A>>
+    {
+        self.get_{{ key }}(id).map(move |value| {
+            ComponentGuard {
+                id,
+                value: Some(value.clone()),
+                changed: false,
+                insert: insert::{{ key }},
+                buf,
+            }
+        })
+    }
+        {% endif %}
+    {% endfor %}
+}
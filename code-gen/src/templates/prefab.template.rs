@@ -0,0 +1,79 @@
+{% if prefabs %}
+#![allow(dead_code)]
+#![allow(unused_imports)]
+
+use entity_store_helper::append::Append;
+use super::{EntityId, EntityChange, insert};
+
+{% for key, prefab in prefabs %}
+/// The components of the `{{ key }}` prefab. `new` initialises each
+/// value to its default from the spec, and values can be overridden
+/// before the prefab is applied to an entity.
+#[derive(Debug, Clone)]
+pub struct {{ prefab.name }}Prefab {
+    {% for c in prefab.components %}
+        {% if c.component.type %}
+            pub {{ c.component.key }}: {{ c.component.type }},
+        {% endif %}
+    {% endfor %}
+}
+
+impl {{ prefab.name }}Prefab {
+    pub fn new() -> Self {
+        Self {
+            {% for c in prefab.components %}
+                {% if c.component.type %}
+                    {{ c.component.key }}: {{ c.value }},
+                {% endif %}
+            {% endfor %}
+        }
+    }
+
+    {% for c in prefab.components %}
+        {% if c.component.type %}
+        pub fn {{ c.component.key }}(self, value: {{ c.component.type }}) -> Self {
+            Self {
+                {{ c.component.key }}: value,
+                ..self
+            }
+        }
+        {% endif %}
+    {% endfor %}
+
+    /// Appends the changes which insert the components of this
+    /// prefab into the entity with the given id.
+    pub fn append_changes<A: Append<EntityChange>>(&self, id: EntityId, buf: &mut A) {
+        {% for c in prefab.components %}
+            {% if c.component.type %}
+                buf.append(insert::{{ c.component.key }}(id, self.{{ c.component.key }}.clone()));
+            {% else %}
+                buf.append(insert::{{ c.component.key }}(id));
+            {% endif %}
+        {% endfor %}
+    }
+
+    pub fn changes(&self, id: EntityId) -> Vec<EntityChange> {
+        let mut changes = Vec::new();
+        self.append_changes(id, &mut changes);
+        changes
+    }
+}
+
+impl Default for {{ prefab.name }}Prefab {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+{% endfor %}
+
+pub mod spawn {
+    use super::*;
+    {% for key, prefab in prefabs %}
+    /// The changes which insert the components of the `{{ key }}`
+    /// prefab, with their default values, into an entity.
+    pub fn {{ key }}(id: EntityId) -> Vec<EntityChange> {
+        {{ prefab.name }}Prefab::new().changes(id)
+    }
+    {% endfor %}
+}
+{% endif %}
@@ -0,0 +1,158 @@
+{% if queries %}
+#![allow(dead_code)]
+#![allow(unused_imports)]
+// queries of only flag components yield a lone id in parentheses
+#![allow(unused_parens, clippy::double_parens)]
+
+use super::{EntityId, EntityStore, ComponentType};
+{% if layout == "archetype" %}
+use super::Archetype;
+{% endif %}
+
+{% if layout == "component" %}
+{% for key, query in queries %}
+pub struct {{ query.name }}QueryIter<'a> {
+    entity_store: &'a EntityStore,
+    ids: Box<dyn Iterator<Item = EntityId> + 'a>,
+}
+
+impl<'a> Iterator for {{ query.name }}QueryIter<'a> {
+    type Item = (EntityId
+        {%- for component in query.components %}{% if component.type %}, &'a {{ component.type }}{% endif %}{% endfor -%}
+        {%- for component in query.optional %}{% if component.type %}, Option<&'a {{ component.type }}>{% else %}, bool{% endif %}{% endfor -%}
+    );
+    fn next(&mut self) -> Option<Self::Item> {
+        for id in self.ids.by_ref() {
+            {% for component in query.components %}
+                {% if component.type %}
+                    let {{ component.key }} = if let Some(value) = self.entity_store.get_{{ component.key }}(id) {
+                        value
+                    } else {
+                        continue;
+                    };
+                {% else %}
+                    if !self.entity_store.contains_{{ component.key }}(id) {
+                        continue;
+                    }
+                {% endif %}
+            {% endfor %}
+            {% for component in query.exclude %}
+                if self.entity_store.contains_{{ component.key }}(id) {
+                    continue;
+                }
+            {% endfor %}
+            {% for component in query.optional %}
+                {% if component.type %}
+                    let {{ component.key }} = self.entity_store.get_{{ component.key }}(id);
+                {% else %}
+                    let {{ component.key }} = self.entity_store.contains_{{ component.key }}(id);
+                {% endif %}
+            {% endfor %}
+            return Some((id
+                {%- for component in query.components %}{% if component.type %}, {{ component.key }}{% endif %}{% endfor -%}
+                {%- for component in query.optional %}, {{ component.key }}{% endfor -%}
+            ));
+        }
+
+        None
+    }
+}
+{% endfor %}
+
+impl EntityStore {
+    {% for key, query in queries %}
+    /// Iterates over entities with all of the components:
+    {%- for component in query.components %} `{{ component.key }}`{% endfor %}.
+    /// Iteration is driven by whichever of these components has the
+    /// smallest storage.
+    pub fn query_{{ key }}(&self) -> {{ query.name }}QueryIter<'_> {
+        let lens = [
+            {% for component in query.components %}
+                self.{{ component.key }}.{{ component.storage.len }}(),
+            {% endfor %}
+        ];
+        let smallest = (0..lens.len()).min_by_key(|&i| lens[i]).unwrap_or(0);
+        let ids: Box<dyn Iterator<Item = EntityId>> = match smallest {
+            {% for component in query.components %}
+                {{ loop.index0 }} => Box::new(self.{{ component.key }}.{{ component.storage.ids }}),
+            {% endfor %}
+            _ => unreachable!(),
+        };
+
+        {{ query.name }}QueryIter {
+            entity_store: self,
+            ids,
+        }
+    }
+    {% endfor %}
+}
+{% else %}
+{% for key, query in queries %}
+pub struct {{ query.name }}QueryIter<'a> {
+    archetypes: &'a [Archetype],
+    archetype_index: usize,
+    row: usize,
+}
+
+impl<'a> {{ query.name }}QueryIter<'a> {
+    fn matches(archetype: &Archetype) -> bool {
+        let component_types = archetype.component_types();
+        {% for component in query.components %}
+            if !component_types.contains(ComponentType::{{ component.name }}) {
+                return false;
+            }
+        {% endfor %}
+        {% for component in query.exclude %}
+            if component_types.contains(ComponentType::{{ component.name }}) {
+                return false;
+            }
+        {% endfor %}
+        true
+    }
+}
+
+impl<'a> Iterator for {{ query.name }}QueryIter<'a> {
+    type Item = (EntityId
+        {%- for component in query.components %}{% if component.type %}, &'a {{ component.type }}{% endif %}{% endfor -%}
+        {%- for component in query.optional %}{% if component.type %}, Option<&'a {{ component.type }}>{% else %}, bool{% endif %}{% endfor -%}
+    );
+    fn next(&mut self) -> Option<Self::Item> {
+        let archetypes = self.archetypes;
+        while let Some(archetype) = archetypes.get(self.archetype_index) {
+            if self.row >= archetype.len() || !Self::matches(archetype) {
+                self.archetype_index += 1;
+                self.row = 0;
+                continue;
+            }
+            let row = self.row;
+            self.row += 1;
+            return Some((archetype.ids()[row]
+                {%- for component in query.components %}{% if component.type %}, &archetype.{{ component.key }}()[row]{% endif %}{% endfor -%}
+                {%- for component in query.optional -%}
+                    {%- if component.type %}, archetype.{{ component.key }}().get(row)
+                    {%- else %}, archetype.component_types().contains(ComponentType::{{ component.name }}){% endif -%}
+                {%- endfor -%}
+            ));
+        }
+
+        None
+    }
+}
+{% endfor %}
+
+impl EntityStore {
+    {% for key, query in queries %}
+    /// Iterates over entities with all of the components:
+    {%- for component in query.components %} `{{ component.key }}`{% endfor %}.
+    /// Archetypes without these components are skipped entirely.
+    pub fn query_{{ key }}(&self) -> {{ query.name }}QueryIter<'_> {
+        {{ query.name }}QueryIter {
+            archetypes: self.archetypes(),
+            archetype_index: 0,
+            row: 0,
+        }
+    }
+    {% endfor %}
+}
+{% endif %}
+{% endif %}
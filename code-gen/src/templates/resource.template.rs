@@ -0,0 +1,80 @@
+{% if resources %}
+#![allow(dead_code)]
+
+use super::EntityChange;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ResourceType {
+    {% for _, resource in resources %}
+        {{ resource.name }},
+    {% endfor %}
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ResourceValue {
+    {% for _, resource in resources %}
+        {{ resource.name }}({{ resource.type }}),
+    {% endfor %}
+}
+
+pub enum ResourceRef<'a> {
+    {% for _, resource in resources %}
+        {{ resource.name }}(&'a {{ resource.type }}),
+    {% endfor %}
+}
+
+impl ResourceValue {
+    pub fn typ(&self) -> ResourceType {
+        match self {
+            {% for _, resource in resources %}
+                &ResourceValue::{{ resource.name }}(_) => ResourceType::{{ resource.name }},
+            {% endfor %}
+        }
+    }
+}
+
+impl<'a> ResourceRef<'a> {
+    pub fn typ(&self) -> ResourceType {
+        match self {
+            {% for _, resource in resources %}
+                &ResourceRef::{{ resource.name }}(_) => ResourceType::{{ resource.name }},
+            {% endfor %}
+        }
+    }
+
+    pub fn to_value(&self) -> ResourceValue {
+        match self {
+            {% for _, resource in resources %}
+                &ResourceRef::{{ resource.name }}(value) => ResourceValue::{{ resource.name }}(value.clone()),
+            {% endfor %}
+        }
+    }
+}
+
+/// Values which belong to the `EntityStore` as a whole rather
+/// than to any one entity. Each resource is `None` until it is set.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Resources {
+    {% for key, resource in resources %}
+        pub {{ key }}: Option<{{ resource.type }}>,
+    {% endfor %}
+}
+
+pub mod set {
+    use super::{ResourceValue, EntityChange};
+    {% for key, resource in resources %}
+    pub fn {{ key }}(value: {{ resource.type }}) -> EntityChange {
+        EntityChange::SetResource(ResourceValue::{{ resource.name }}(value))
+    }
+    {% endfor %}
+}
+
+pub mod clear {
+    use super::{ResourceType, EntityChange};
+    {% for key, resource in resources %}
+    pub fn {{ key }}() -> EntityChange {
+        EntityChange::ClearResource(ResourceType::{{ resource.name }})
+    }
+    {% endfor %}
+}
+{% endif %}
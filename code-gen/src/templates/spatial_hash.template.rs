@@ -1,4 +1,4 @@
-{% if spatial_hash %}
+{% if spatial_hashes %}
 #![allow(unused_variables)]
 #![allow(unused_imports)]
 #![allow(dead_code)]
@@ -6,17 +6,19 @@
 
 use super::{EntityChange, EntityStore, EntityId, ComponentType, ComponentValue};
 use entity_store_helper::num::One;
-use entity_store_helper::direction::Directions;
+use entity_store_helper::direction::{Directions, DirectionsCardinal, DirectionsOrdinal};
 
 use entity_store_helper::grid_2d;
 pub use entity_store_helper::grid_2d::{Grid, Size, Coord};
 pub use entity_store_helper::grid_2d::coord_system::XThenYIter as CoordIter;
+pub use entity_store_helper::{Region, RegionCoordIter};
 
-pub type Iter<'a> = grid_2d::GridIter<'a, SpatialHashCell>;
-pub type CoordEnumerate<'a> = grid_2d::GridEnumerate<'a, SpatialHashCell>;
+{% for spatial_hash in spatial_hashes %}
+pub type {{ spatial_hash.iter_prefix }}Iter<'a> = grid_2d::GridIter<'a, {{ spatial_hash.name }}SpatialHashCell>;
+pub type {{ spatial_hash.iter_prefix }}CoordEnumerate<'a> = grid_2d::GridEnumerate<'a, {{ spatial_hash.name }}SpatialHashCell>;
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
-pub struct SpatialHashCell {
+pub struct {{ spatial_hash.name }}SpatialHashCell {
     {% for key, field in spatial_hash.fields %}
         {% if field.aggregate %}
             pub {{ key }}: {{ field.aggregate.rust_type }},
@@ -25,14 +27,28 @@ pub struct SpatialHashCell {
     pub last_updated: u64,
 }
 
-impl SpatialHashCell {
+impl {{ spatial_hash.name }}SpatialHashCell {
+    {% for _, field in spatial_hash.fields %}
+        {% if field.aggregate.type == "flags" %}
+            {% for component in field.flags %}
+                /// The bit of `{{ field.key }}` which is set while any entity
+                /// in the cell has the `{{ component.key }}` component.
+                pub const {{ component.key | upper }}_FLAG: u64 = 1 << {{ loop.index0 }};
+
+                pub fn has_{{ component.key }}(&self) -> bool {
+                    self.{{ field.key }}.has({{ loop.index0 }})
+                }
+            {% endfor %}
+        {% endif %}
+    {% endfor %}
+
     fn insert(&mut self, id: EntityId, entity_store: &EntityStore, time: u64) {
         {% for _, by_component in spatial_hash.by_component %}
             {% if by_component.lookup %}
                 {% if by_component.lookup == "get" %}
-                    if let Some(current) = entity_store.{{ by_component.component.key }}.get(&id) {
+                    if let Some(current) = entity_store.get_{{ by_component.component.key }}(id) {
                 {% else %}
-                    if entity_store.{{ by_component.component.key }}.{{ by_component.component.contains }}(&id) {
+                    if entity_store.contains_{{ by_component.component.key }}(id) {
                 {% endif %}
 
                 {% for _, field in by_component.fields %}
@@ -42,6 +58,12 @@ impl SpatialHashCell {
                         self.{{ field.key }} += {{ field.aggregate.rust_type }}::one();
                     {% elif field.aggregate.type == "set" %}
                         self.{{ field.key }}.insert(id);
+                    {% elif field.aggregate.type == "min" or field.aggregate.type == "max" %}
+                        self.{{ field.key }}.insert(current.clone());
+                    {% elif field.aggregate.type == "any" %}
+                        self.{{ field.key }}.insert();
+                    {% elif field.aggregate.type == "flags" %}
+                        self.{{ field.key }}.insert({{ field.bit }});
                     {% endif %}
                 {% endfor %}
 
@@ -54,9 +76,9 @@ impl SpatialHashCell {
         {% for _, by_component in spatial_hash.by_component %}
             {% if by_component.lookup %}
                 {% if by_component.lookup == "get" %}
-                    if let Some(current) = entity_store.{{ by_component.component.key }}.get(&id) {
+                    if let Some(current) = entity_store.get_{{ by_component.component.key }}(id) {
                 {% else %}
-                    if entity_store.{{ by_component.component.key }}.{{ by_component.component.contains }}(&id) {
+                    if entity_store.contains_{{ by_component.component.key }}(id) {
                 {% endif %}
 
                 {% for _, field in by_component.fields %}
@@ -66,6 +88,12 @@ impl SpatialHashCell {
                         self.{{ field.key }} -= {{ field.aggregate.rust_type }}::one();
                     {% elif field.aggregate.type == "set" %}
                         self.{{ field.key }}.remove(&id);
+                    {% elif field.aggregate.type == "min" or field.aggregate.type == "max" %}
+                        self.{{ field.key }}.remove(current);
+                    {% elif field.aggregate.type == "any" %}
+                        self.{{ field.key }}.remove();
+                    {% elif field.aggregate.type == "flags" %}
+                        self.{{ field.key }}.remove({{ field.bit }});
                     {% endif %}
                 {% endfor %}
 
@@ -76,12 +104,26 @@ impl SpatialHashCell {
     }
 }
 
+pub struct {{ spatial_hash.iter_prefix }}RegionIter<'a> {
+    grid: &'a Grid<{{ spatial_hash.name }}SpatialHashCell>,
+    coords: RegionCoordIter,
+}
+
+impl<'a> Iterator for {{ spatial_hash.iter_prefix }}RegionIter<'a> {
+    type Item = (Coord, &'a {{ spatial_hash.name }}SpatialHashCell);
+    fn next(&mut self) -> Option<Self::Item> {
+        // coordinates are clipped to the grid so are always valid
+        let grid = self.grid;
+        self.coords.next().map(|coord| (coord, grid.get_checked(coord)))
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SpatialHashTable {
-    grid: Grid<SpatialHashCell>,
+pub struct {{ spatial_hash.name }}SpatialHashTable {
+    grid: Grid<{{ spatial_hash.name }}SpatialHashCell>,
 }
 
-impl SpatialHashTable {
+impl {{ spatial_hash.name }}SpatialHashTable {
     pub fn new(size: Size) -> Self {
         Self {
             grid: Grid::new_default(size),
@@ -100,7 +142,7 @@ impl SpatialHashTable {
         self.grid.size()
     }
 
-    pub fn iter(&self) -> Iter {
+    pub fn iter(&self) -> {{ spatial_hash.iter_prefix }}Iter<'_> {
         self.grid.iter()
     }
 
@@ -108,24 +150,109 @@ impl SpatialHashTable {
         self.grid.coord_iter()
     }
 
-    pub fn enumerate(&self) -> CoordEnumerate {
+    pub fn enumerate(&self) -> {{ spatial_hash.iter_prefix }}CoordEnumerate<'_> {
         self.grid.enumerate()
     }
 
-    pub fn get<T: Into<Coord>>(&self, coord: T) -> Option<&SpatialHashCell> {
+    pub fn get<T: Into<Coord>>(&self, coord: T) -> Option<&{{ spatial_hash.name }}SpatialHashCell> {
         self.grid.get(coord.into())
     }
 
-    fn get_mut<T: Into<Coord>>(&mut self, coord: T) -> Option<&mut SpatialHashCell> {
+    fn get_mut<T: Into<Coord>>(&mut self, coord: T) -> Option<&mut {{ spatial_hash.name }}SpatialHashCell> {
         self.grid.get_mut(coord.into())
     }
 
+    /// Iterates over the cells in a region, skipping any
+    /// part of the region which lies outside the grid.
+    pub fn region(&self, region: Region) -> {{ spatial_hash.iter_prefix }}RegionIter<'_> {
+        {{ spatial_hash.iter_prefix }}RegionIter {
+            grid: &self.grid,
+            coords: region.clipped_coords(self.grid.size()),
+        }
+    }
+
+    pub fn rect(&self, top_left: Coord, size: Size) -> {{ spatial_hash.iter_prefix }}RegionIter<'_> {
+        self.region(Region::Rect(top_left, size))
+    }
+
+    pub fn chebyshev_radius(&self, centre: Coord, radius: u32) -> {{ spatial_hash.iter_prefix }}RegionIter<'_> {
+        self.region(Region::Chebyshev(centre, radius))
+    }
+
+    pub fn euclidean_radius(&self, centre: Coord, radius: u32) -> {{ spatial_hash.iter_prefix }}RegionIter<'_> {
+        self.region(Region::Euclidean(centre, radius))
+    }
+
+    {% for _, field in spatial_hash.fields %}
+        {% if field.aggregate.type == "set" %}
+        /// Iterates over the entities in the `{{ field.key }}` sets
+        /// of all the cells in a region.
+        pub fn {{ field.key }}_in_region<'a>(&'a self, region: Region) -> Box<dyn Iterator<Item = EntityId> + 'a> {
+            Box::new(self.region(region).flat_map(|(_, cell)| cell.{{ field.key }}.iter().cloned()))
+        }
+        {% endif %}
+    {% endfor %}
+
+    /// Returns true if changing a component of the given type
+    /// may change the contents of the spatial hash.
+    pub fn affected_by(component_type: ComponentType) -> bool {
+        component_type == ComponentType::{{ spatial_hash.position_component.name }}
+            {%- for _, by_component in spatial_hash.by_component %}
+            || component_type == ComponentType::{{ by_component.component.name }}
+            {%- endfor %}
+    }
+
+    /// Removes all the given entities' contributions to the spatial hash,
+    /// based on their components in the given store.
+    pub fn remove_entities<I: IntoIterator<Item = EntityId>>(&mut self, entity_store: &EntityStore, ids: I, time: u64) {
+        for id in ids {
+            if let Some(current) = entity_store.get_{{ spatial_hash.position_component.key }}(id) {
+                if let Some(cell) = self.grid.get_mut(*current) {
+                    cell.remove(id, entity_store, time);
+                }
+                {% if spatial_hash.has_neighbours %}
+                self.remove_neighbours(id, entity_store, time, *current);
+                {% endif %}
+            }
+        }
+    }
+
+    /// Adds the given entities' contributions to the spatial hash,
+    /// based on their components in the given store.
+    pub fn insert_entities<I: IntoIterator<Item = EntityId>>(&mut self, entity_store: &EntityStore, ids: I, time: u64) {
+        for id in ids {
+            if let Some(current) = entity_store.get_{{ spatial_hash.position_component.key }}(id) {
+                if let Some(cell) = self.grid.get_mut(*current) {
+                    cell.insert(id, entity_store, time);
+                }
+                {% if spatial_hash.has_neighbours %}
+                self.insert_neighbours(id, entity_store, time, *current);
+                {% endif %}
+            }
+        }
+    }
+
+    /// Updates the spatial hash for a change which is about to be
+    /// committed to the given store.
+    {%- if unique_components %}
+    /// A change which gives a unique component to a different entity
+    /// also updates the spatial hash for the component's removal from
+    /// its previous owner, or is ignored if the store would reject it.
+    {%- endif %}
     pub fn update(&mut self, entity_store: &EntityStore, change: &EntityChange, time: u64) {
+        {% if unique_components %}
+        if entity_store.check_unique(change).is_err() {
+            return;
+        }
+        if let Some(removal) = entity_store.implied_removal(change) {
+            self.update(entity_store, &removal, time);
+        }
+        {% endif %}
         match change {
             &EntityChange::Insert(id, ref value) => {
                 match value {
                     &ComponentValue::{{ spatial_hash.position_component.name }}(position) => {
-                        if let Some(current) = entity_store.{{ spatial_hash.position_component.key }}.get(&id) {
+                        if let Some(current) = entity_store.get_{{ spatial_hash.position_component.key }}(id) {
                             if let Some(cell) = self.grid.get_mut(*current) {
                                 cell.remove(id, entity_store, time);
                             }
@@ -142,46 +269,66 @@ impl SpatialHashTable {
                     }
                     {% for _, by_component in spatial_hash.by_component %}
                         {% if by_component.component.type %}
-                            &ComponentValue::{{ by_component.component.name }}(value) => {
+                            &ComponentValue::{{ by_component.component.name }}(ref value) => {
                         {% else %}
                             &ComponentValue::{{ by_component.component.name }} => {
                         {% endif %}
-                            if let Some(position) = entity_store.{{ spatial_hash.position_component.key }}.get(&id) {
+                            if let Some(position) = entity_store.get_{{ spatial_hash.position_component.key }}(id) {
                                 {% for _, field in by_component.fields %}
                                     {% if field.aggregate.type == "neighbour_count" %}
-                                        if !entity_store.{{ by_component.component.key }}.{{ by_component.component.contains }}(&id) {
-                                            for d in Directions {
+                                        if !entity_store.contains_{{ by_component.component.key }}(id) {
+                                            for d in {{ field.directions }} {
                                                 if let Some(cell) = self.grid.get_mut(*position + d.coord()) {
                                                     cell.{{ field.key }}.inc(d.opposite());
+                                                    cell.last_updated = time;
                                                 }
                                             }
                                         }
-                                        cell.last_updated = time;
+                                    {% elif field.aggregate.type == "neighbour_sum" %}
+                                        let current = entity_store.get_{{ by_component.component.key }}(id);
+                                        for d in {{ field.directions }} {
+                                            if let Some(cell) = self.grid.get_mut(*position + d.coord()) {
+                                                if let Some(current) = current {
+                                                    cell.{{ field.key }}.sub(d.opposite(), *current);
+                                                }
+                                                cell.{{ field.key }}.add(d.opposite(), *value);
+                                                cell.last_updated = time;
+                                            }
+                                        }
                                     {% endif %}
                                 {% endfor %}
 
                                 {% if by_component.lookup %}
                                     if let Some(cell) = self.grid.get_mut(*position) {
                                         {% if by_component.lookup == "get" %}
-                                            if let Some(current) = entity_store.{{ by_component.component.key }}.get(&id) {
+                                            if let Some(current) = entity_store.get_{{ by_component.component.key }}(id) {
                                                 {% for _, field in by_component.fields %}
                                                     {% if field.aggregate.type == "total" %}
-                                                        let increase = value - *current;
+                                                        let increase = *value - *current;
                                                         cell.{{ field.key }} += increase;
+                                                    {% elif field.aggregate.type == "min" or field.aggregate.type == "max" %}
+                                                        cell.{{ field.key }}.remove(current);
+                                                        cell.{{ field.key }}.insert(value.clone());
                                                     {% endif %}
                                                 {% endfor %}
                                             } else {
                                         {% else %}
-                                            if !entity_store.{{ by_component.component.key }}.{{ by_component.component.contains }}(&id) {
+                                            if !entity_store.contains_{{ by_component.component.key }}(id) {
                                         {% endif %}
 
                                         {% for _, field in by_component.fields %}
                                             {% if field.aggregate.type == "total" %}
-                                                cell.{{ field.key }} += value;
+                                                cell.{{ field.key }} += *value;
                                             {% elif field.aggregate.type == "count" %}
                                                 cell.{{ field.key }} += {{ field.aggregate.rust_type }}::one();
                                             {% elif field.aggregate.type == "set" %}
                                                 cell.{{ field.key }}.insert(id);
+                                            {% elif field.aggregate.type == "min" or field.aggregate.type == "max" %}
+                                                cell.{{ field.key }}.insert(value.clone());
+                                            {% elif field.aggregate.type == "any" %}
+                                                cell.{{ field.key }}.insert();
+                                            {% elif field.aggregate.type == "flags" %}
+                                                cell.{{ field.key }}.insert({{ field.bit }});
                                             {% endif %}
                                         {% endfor %}
                                         }
@@ -197,7 +344,7 @@ impl SpatialHashTable {
             &EntityChange::Remove(id, typ) => {
                 match typ {
                     ComponentType::{{ spatial_hash.position_component.name }} => {
-                        if let Some(current) = entity_store.{{ spatial_hash.position_component.key }}.get(&id) {
+                        if let Some(current) = entity_store.get_{{ spatial_hash.position_component.key }}(id) {
                             if let Some(cell) = self.grid.get_mut(*current) {
                                 cell.remove(id, entity_store, time);
                             }
@@ -208,26 +355,35 @@ impl SpatialHashTable {
                     }
                     {% for _, by_component in spatial_hash.by_component %}
                         ComponentType::{{ by_component.component.name }} => {
-                            if let Some(position) = entity_store.{{ spatial_hash.position_component.key }}.get(&id) {
+                            if let Some(position) = entity_store.get_{{ spatial_hash.position_component.key }}(id) {
                                 {% for _, field in by_component.fields %}
                                     {% if field.aggregate.type == "neighbour_count" %}
-                                        if entity_store.{{ by_component.component.key }}.{{ by_component.component.contains }}(&id) {
-                                            for d in Directions {
-                                                if let Some(cell) = self.grid.get_mut(*position + d.vector()) {
+                                        if entity_store.contains_{{ by_component.component.key }}(id) {
+                                            for d in {{ field.directions }} {
+                                                if let Some(cell) = self.grid.get_mut(*position + d.coord()) {
                                                     cell.{{ field.key }}.dec(d.opposite());
+                                                    cell.last_updated = time;
+                                                }
+                                            }
+                                        }
+                                    {% elif field.aggregate.type == "neighbour_sum" %}
+                                        if let Some(current) = entity_store.get_{{ by_component.component.key }}(id) {
+                                            for d in {{ field.directions }} {
+                                                if let Some(cell) = self.grid.get_mut(*position + d.coord()) {
+                                                    cell.{{ field.key }}.sub(d.opposite(), *current);
+                                                    cell.last_updated = time;
                                                 }
                                             }
                                         }
-                                        cell.last_updated = time;
                                     {% endif %}
                                 {% endfor %}
 
                                 {% if by_component.lookup %}
                                     if let Some(cell) = self.grid.get_mut(*position) {
                                         {% if by_component.lookup == "get" %}
-                                            if let Some(current) = entity_store.{{ by_component.component.key }}.get(&id) {
+                                            if let Some(current) = entity_store.get_{{ by_component.component.key }}(id) {
                                         {% else %}
-                                            if entity_store.{{ by_component.component.key }}.{{ by_component.component.contains }}(&id) {
+                                            if entity_store.contains_{{ by_component.component.key }}(id) {
                                         {% endif %}
 
                                         {% for _, field in by_component.fields %}
@@ -237,6 +393,12 @@ impl SpatialHashTable {
                                                 cell.{{ field.key }} -= {{ field.aggregate.rust_type }}::one();
                                             {% elif field.aggregate.type == "set" %}
                                                 cell.{{ field.key }}.remove(&id);
+                                            {% elif field.aggregate.type == "min" or field.aggregate.type == "max" %}
+                                                cell.{{ field.key }}.remove(current);
+                                            {% elif field.aggregate.type == "any" %}
+                                                cell.{{ field.key }}.remove();
+                                            {% elif field.aggregate.type == "flags" %}
+                                                cell.{{ field.key }}.remove({{ field.bit }});
                                             {% endif %}
                                         {% endfor %}
                                         }
@@ -249,6 +411,9 @@ impl SpatialHashTable {
                     _ => {}
                 }
             }
+            {% if resources %}
+            _ => {}
+            {% endif %}
         }
     }
 
@@ -256,14 +421,23 @@ impl SpatialHashTable {
         fn insert_neighbours(&mut self, id: EntityId, entity_store: &EntityStore, time: u64, coord: Coord) {
             {% for _, field in spatial_hash.fields %}
                 {% if field.aggregate.type == "neighbour_count" %}
-                    if entity_store.{{ field.component.key }}.{{ field.component.contains }}(&id) {
-                        for d in Directions {
+                    if entity_store.contains_{{ field.component.key }}(id) {
+                        for d in {{ field.directions }} {
                             if let Some(cell) = self.grid.get_mut(coord + d.coord()) {
                                 cell.{{ field.key }}.inc(d.opposite());
+                                cell.last_updated = time;
+                            }
+                        }
+                    }
+                {% elif field.aggregate.type == "neighbour_sum" %}
+                    if let Some(value) = entity_store.get_{{ field.component.key }}(id) {
+                        for d in {{ field.directions }} {
+                            if let Some(cell) = self.grid.get_mut(coord + d.coord()) {
+                                cell.{{ field.key }}.add(d.opposite(), *value);
+                                cell.last_updated = time;
                             }
                         }
                     }
-                    cell.last_updated = time;
                 {% endif %}
             {% endfor %}
         }
@@ -271,17 +445,27 @@ impl SpatialHashTable {
         fn remove_neighbours(&mut self, id: EntityId, entity_store: &EntityStore, time: u64, coord: Coord) {
             {% for _, field in spatial_hash.fields %}
                 {% if field.aggregate.type == "neighbour_count" %}
-                    if entity_store.{{ field.component.key }}.{{ field.component.contains }}(&id) {
-                        for d in Directions {
+                    if entity_store.contains_{{ field.component.key }}(id) {
+                        for d in {{ field.directions }} {
                             if let Some(cell) = self.grid.get_mut(coord + d.coord()) {
                                 cell.{{ field.key }}.dec(d.opposite());
+                                cell.last_updated = time;
+                            }
+                        }
+                    }
+                {% elif field.aggregate.type == "neighbour_sum" %}
+                    if let Some(value) = entity_store.get_{{ field.component.key }}(id) {
+                        for d in {{ field.directions }} {
+                            if let Some(cell) = self.grid.get_mut(coord + d.coord()) {
+                                cell.{{ field.key }}.sub(d.opposite(), *value);
+                                cell.last_updated = time;
                             }
                         }
                     }
-                    cell.last_updated = time;
                 {% endif %}
             {% endfor %}
         }
     {% endif %}
 }
+{% endfor %}
 {% endif %}
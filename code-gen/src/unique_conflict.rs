@@ -0,0 +1,29 @@
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UniqueConflict {
+    Replace,
+    Error,
+}
+
+use self::UniqueConflict::*;
+
+pub const ALL: &[UniqueConflict] = &[
+    Replace,
+    Error,
+];
+
+impl UniqueConflict {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "replace" => Some(Replace),
+            "error" => Some(Error),
+            _ => None,
+        }
+    }
+
+    pub fn to_str(self) -> &'static str {
+        match self {
+            Replace => "replace",
+            Error => "error",
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::BTreeMap;
+
+/// Multiset of values, from which the smallest and largest
+/// values can be found after any value is removed.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Multiset<T: Ord> {
+    counts: BTreeMap<T, usize>,
+}
+
+impl<T: Ord> Multiset<T> {
+    pub fn new() -> Self {
+        Multiset {
+            counts: BTreeMap::new(),
+        }
+    }
+
+    pub fn insert(&mut self, value: T) {
+        *self.counts.entry(value).or_insert(0) += 1;
+    }
+
+    /// Removes one occurrence of a value, returning true
+    /// if the value was present.
+    pub fn remove(&mut self, value: &T) -> bool {
+        let remaining = if let Some(count) = self.counts.get_mut(value) {
+            *count -= 1;
+            *count
+        } else {
+            return false;
+        };
+        if remaining == 0 {
+            self.counts.remove(value);
+        }
+        true
+    }
+
+    pub fn min(&self) -> Option<&T> {
+        self.counts.keys().next()
+    }
+
+    pub fn max(&self) -> Option<&T> {
+        self.counts.keys().next_back()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+}
+
+// implemented by hand as deriving would require `T: Default`
+impl<T: Ord> Default for Multiset<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Storage for the `min` aggregate of the generated spatial hash.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Min<T: Ord>(Multiset<T>);
+
+impl<T: Ord> Min<T> {
+    pub fn insert(&mut self, value: T) {
+        self.0.insert(value);
+    }
+
+    pub fn remove(&mut self, value: &T) {
+        self.0.remove(value);
+    }
+
+    pub fn get(&self) -> Option<&T> {
+        self.0.min()
+    }
+}
+
+impl<T: Ord> Default for Min<T> {
+    fn default() -> Self {
+        Min(Multiset::new())
+    }
+}
+
+/// Storage for the `max` aggregate of the generated spatial hash.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Max<T: Ord>(Multiset<T>);
+
+impl<T: Ord> Max<T> {
+    pub fn insert(&mut self, value: T) {
+        self.0.insert(value);
+    }
+
+    pub fn remove(&mut self, value: &T) {
+        self.0.remove(value);
+    }
+
+    pub fn get(&self) -> Option<&T> {
+        self.0.max()
+    }
+}
+
+impl<T: Ord> Default for Max<T> {
+    fn default() -> Self {
+        Max(Multiset::new())
+    }
+}
+
+/// Storage for the `any` aggregate of the generated spatial hash.
+/// A count is kept so that removing one of several entities
+/// with a component leaves the aggregate set.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct Any {
+    count: usize,
+}
+
+impl Any {
+    pub fn insert(&mut self) {
+        self.count += 1;
+    }
+
+    pub fn remove(&mut self) {
+        self.count -= 1;
+    }
+
+    pub fn get(&self) -> bool {
+        self.count != 0
+    }
+}
+
+/// Storage for the `flags` aggregate of the generated spatial hash.
+/// Each bit is set while at least one entity in the cell has the
+/// corresponding component, and a count is kept for each bit so
+/// that removing one of several such entities leaves it set.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Flags {
+    bits: u64,
+    counts: Vec<usize>,
+}
+
+impl Flags {
+    pub fn insert(&mut self, bit: usize) {
+        if bit >= self.counts.len() {
+            self.counts.resize(bit + 1, 0);
+        }
+        self.counts[bit] += 1;
+        self.bits |= 1 << bit;
+    }
+
+    pub fn remove(&mut self, bit: usize) {
+        self.counts[bit] -= 1;
+        if self.counts[bit] == 0 {
+            self.bits &= !(1 << bit);
+        }
+    }
+
+    /// All the flags packed into a single value, where bit `n`
+    /// corresponds to the `n`th component of the aggregate.
+    pub fn bits(&self) -> u64 {
+        self.bits
+    }
+
+    pub fn has(&self, bit: usize) -> bool {
+        self.bits & (1 << bit) != 0
+    }
+
+    pub fn count(&self, bit: usize) -> usize {
+        self.counts.get(bit).cloned().unwrap_or(0)
+    }
+}
@@ -0,0 +1,40 @@
+use num::Integer;
+use num::traits::{Bounded, WrappingSub};
+
+/// Id made up of an index and a generation counter.
+/// The index identifies a slot which may be reused once the id
+/// is freed. The generation is incremented each time the slot
+/// is reused, so ids referring to a freed entity can be told
+/// apart from ids of the entity which replaced it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct GenerationalId<T> {
+    index: T,
+    generation: T,
+}
+
+impl<T: Integer + Copy> GenerationalId<T> {
+    pub fn new(index: T, generation: T) -> Self {
+        Self {
+            index,
+            generation,
+        }
+    }
+
+    pub fn index(&self) -> T {
+        self.index
+    }
+
+    pub fn generation(&self) -> T {
+        self.generation
+    }
+}
+
+impl<T: Integer + Bounded + WrappingSub + Copy> GenerationalId<T> {
+    /// Returns true if this id's generation is older than the other's.
+    /// Generations wrap around, so a generation counts as older if it's
+    /// at most half the range of generations behind the other.
+    pub fn is_older_than(&self, other: Self) -> bool {
+        let behind = other.generation.wrapping_sub(&self.generation);
+        behind != T::zero() && behind <= T::max_value() / (T::one() + T::one())
+    }
+}
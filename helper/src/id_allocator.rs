@@ -1,25 +1,121 @@
-use num::Integer;
+use num::{Integer, ToPrimitive};
+use num::traits::WrappingAdd;
+use serde::{Deserialize, Deserializer};
+use generational_id::GenerationalId;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct IdAllocator<T: Integer + Copy>  {
+/// Types of id which can be handed out by an `IdAllocator`.
+pub trait AllocatorId: Copy + PartialEq {
+    /// The first id to be allocated.
+    fn first() -> Self;
+    /// The id to allocate after this one, when no freed ids are available.
+    fn next(self) -> Self;
+    /// The id to allocate in place of this one after it has been freed.
+    fn reuse(self) -> Self;
+    /// The slot this id occupies. Ids which reuse a freed
+    /// id occupy the same slot as the freed id.
+    fn slot(self) -> usize;
+}
+
+impl<T: Integer + ToPrimitive + Copy> AllocatorId for T {
+
+    fn first() -> Self {
+        T::zero()
+    }
+
+    fn next(self) -> Self {
+        self + T::one()
+    }
+
+    fn reuse(self) -> Self {
+        self
+    }
+
+    fn slot(self) -> usize {
+        self.to_usize().expect("Id too large to be used as a slot")
+    }
+}
+
+impl<T: Integer + ToPrimitive + WrappingAdd + Copy> AllocatorId for GenerationalId<T> {
+
+    fn first() -> Self {
+        GenerationalId::new(T::zero(), T::zero())
+    }
+
+    fn next(self) -> Self {
+        GenerationalId::new(self.index() + T::one(), T::zero())
+    }
+
+    fn reuse(self) -> Self {
+        // generations wrap around rather than overflowing
+        GenerationalId::new(self.index(), self.generation().wrapping_add(&T::one()))
+    }
+
+    fn slot(self) -> usize {
+        self.index().to_usize().expect("Id too large to be used as a slot")
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IdAllocator<T: AllocatorId>  {
+    next: T,
+    free_list: Vec<T>,
+    // the id occupying each slot, or `None` if the slot has been freed
+    slots: Vec<Option<T>>,
+}
+
+// The serialized form of an `IdAllocator`. Allocators serialized before
+// `slots` was added don't have it, in which case it's rebuilt, as
+// a missing `Option` field is deserialized as `None`.
+#[derive(Deserialize)]
+struct SerializedIdAllocator<T> {
     next: T,
     free_list: Vec<T>,
+    slots: Option<Vec<Option<T>>>,
 }
 
-impl<T: Integer + Copy> IdAllocator<T> {
+impl<'de, T: AllocatorId + Deserialize<'de>> Deserialize<'de> for IdAllocator<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let SerializedIdAllocator { next, free_list, slots } = SerializedIdAllocator::deserialize(deserializer)?;
+        let slots = slots.unwrap_or_else(|| Self::rebuild_slots(next, &free_list));
+        Ok(Self {
+            next,
+            free_list,
+            slots,
+        })
+    }
+}
+
+impl<T: AllocatorId> IdAllocator<T> {
     pub fn new() -> Self {
         Self {
-            next: T::zero(),
+            next: T::first(),
             free_list: Vec::new(),
+            slots: Vec::new(),
         }
     }
 
+    // Every id before `next` occupies its slot unless it's been freed.
+    fn rebuild_slots(next: T, free_list: &[T]) -> Vec<Option<T>> {
+        let mut slots = Vec::new();
+        let mut id = T::first();
+        while id != next {
+            slots.push(Some(id));
+            id = id.next();
+        }
+        for id in free_list {
+            slots[id.slot()] = None;
+        }
+        slots
+    }
+
     pub fn allocate(&mut self) -> T {
         if let Some(id) = self.free_list.pop() {
+            self.slots[id.slot()] = Some(id);
             id
         } else {
             let id = self.next;
-            self.next = self.next + T::one();
+            self.next = self.next.next();
+            self.slots.push(Some(id));
             id
         }
     }
@@ -28,7 +124,25 @@ impl<T: Integer + Copy> IdAllocator<T> {
         self.next
     }
 
+    /// Returns true if the id has been allocated and not since freed.
+    pub fn is_allocated(&self, id: T) -> bool {
+        self.slots.get(id.slot()) == Some(&Some(id))
+    }
+
+    /// Frees an id so its slot can be reused. Ids which aren't
+    /// currently allocated are ignored, so freeing an id twice
+    /// doesn't hand its slot out twice.
     pub fn free(&mut self, id: T) {
-        self.free_list.push(id);
+        match self.slots.get_mut(id.slot()) {
+            Some(slot) if *slot == Some(id) => *slot = None,
+            _ => return,
+        }
+        self.free_list.push(id.reuse());
+    }
+}
+
+impl<T: AllocatorId> Default for IdAllocator<T> {
+    fn default() -> Self {
+        Self::new()
     }
 }
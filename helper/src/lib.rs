@@ -12,18 +12,28 @@ pub extern crate grid_2d;
 
 mod neighbour_count;
 mod id_allocator;
+mod generational_id;
+mod region;
+mod aggregate;
 
-pub use self::neighbour_count::NeighbourCount;
-pub use self::id_allocator::IdAllocator;
+pub use self::neighbour_count::{Neighbours, NeighbourCount};
+pub use self::id_allocator::{IdAllocator, AllocatorId};
+pub use self::generational_id::GenerationalId;
+pub use self::region::{Region, RegionCoordIter};
+pub use self::aggregate::{Multiset, Min, Max, Any, Flags};
 
 /// Expands to the code generated by
 /// [entity_store_code_gen](https://crates.io/crates/entity_store_code_gen).
 /// Example usage:
 ///
 /// ```
+/// #[macro_use] extern crate entity_store_helper;
+/// # macro_rules! include { ($path:expr) => {} }
+///
 /// mod entity_store {
-///     include_entity_store();
+///     include_entity_store!("entity_store.rs");
 /// }
+/// # fn main() {}
 /// ```
 #[macro_export]
 macro_rules! include_entity_store {
@@ -1,47 +1,43 @@
+use std::ops::{AddAssign, SubAssign};
+use num::{Zero, One};
 use direction::{Direction, DirectionBitmap, NUM_DIRECTIONS};
 
 const NUM_NEIGHBOURS: usize = NUM_DIRECTIONS;
 
-/// Table for managing counts associated with directions.
-/// This is used by the generated spatial hash as
-/// storage for the `neighbour_count` aggregate.
+/// Table for managing values associated with directions.
+/// This is used by the generated spatial hash as storage
+/// for the `neighbour_count` and `neighbour_sum` aggregates.
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
-pub struct NeighbourCount {
-    neighbours: [u8; NUM_NEIGHBOURS],
+pub struct Neighbours<T> {
+    neighbours: [T; NUM_NEIGHBOURS],
 }
 
-impl NeighbourCount {
+/// Storage for the `neighbour_count` aggregate.
+pub type NeighbourCount = Neighbours<u8>;
+
+impl<T: Copy + Zero> Neighbours<T> {
     pub fn new() -> Self {
-        NeighbourCount {
-            neighbours: [0; NUM_NEIGHBOURS],
+        Neighbours {
+            neighbours: [T::zero(); NUM_NEIGHBOURS],
         }
     }
 
-    pub fn inc(&mut self, direction: Direction) {
-        self.neighbours[direction as usize] += 1;
-    }
-
-    pub fn dec(&mut self, direction: Direction) {
-        self.neighbours[direction as usize] -= 1;
-    }
-
-    pub fn get(&self, direction: Direction) -> u8 {
+    pub fn get(&self, direction: Direction) -> T {
         self.neighbours[direction as usize]
     }
 
     pub fn has(&self, direction: Direction) -> bool {
-        self.neighbours[direction as usize] != 0
+        !self.neighbours[direction as usize].is_zero()
     }
 
     pub fn bitmap_raw(&self) -> u8 {
-        (((self.neighbours[0] != 0) as u8) << 0) |
-        (((self.neighbours[1] != 0) as u8) << 1) |
-        (((self.neighbours[2] != 0) as u8) << 2) |
-        (((self.neighbours[3] != 0) as u8) << 3) |
-        (((self.neighbours[4] != 0) as u8) << 4) |
-        (((self.neighbours[5] != 0) as u8) << 5) |
-        (((self.neighbours[6] != 0) as u8) << 6) |
-        (((self.neighbours[7] != 0) as u8) << 7)
+        let mut bitmap = 0;
+        for (i, value) in self.neighbours.iter().enumerate() {
+            if !value.is_zero() {
+                bitmap |= 1 << i;
+            }
+        }
+        bitmap
     }
 
     pub fn bitmap(&self) -> DirectionBitmap {
@@ -49,7 +45,36 @@ impl NeighbourCount {
     }
 }
 
-impl Default for NeighbourCount {
+impl<T: Copy + Zero + AddAssign + SubAssign> Neighbours<T> {
+    pub fn add(&mut self, direction: Direction, value: T) {
+        self.neighbours[direction as usize] += value;
+    }
+
+    pub fn sub(&mut self, direction: Direction, value: T) {
+        self.neighbours[direction as usize] -= value;
+    }
+
+    /// Sum of the values in all directions.
+    pub fn total(&self) -> T {
+        let mut total = T::zero();
+        for value in self.neighbours.iter() {
+            total += *value;
+        }
+        total
+    }
+}
+
+impl<T: Copy + Zero + One + AddAssign + SubAssign> Neighbours<T> {
+    pub fn inc(&mut self, direction: Direction) {
+        self.add(direction, T::one());
+    }
+
+    pub fn dec(&mut self, direction: Direction) {
+        self.sub(direction, T::one());
+    }
+}
+
+impl<T: Copy + Zero> Default for Neighbours<T> {
     fn default() -> Self {
         Self::new()
     }
@@ -0,0 +1,107 @@
+use grid_2d::{Coord, Size};
+
+/// A set of coordinates, used to query the cells of a
+/// generated spatial hash which lie within an area.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Region {
+    /// Rectangle with the given top-left corner and size.
+    Rect(Coord, Size),
+    /// Coordinates whose chebyshev (chessboard) distance
+    /// from the centre is at most the radius.
+    Chebyshev(Coord, u32),
+    /// Coordinates whose euclidean distance from the
+    /// centre is at most the radius.
+    Euclidean(Coord, u32),
+}
+
+impl Region {
+    pub fn contains(&self, coord: Coord) -> bool {
+        match *self {
+            Region::Rect(top_left, size) => {
+                let (top_left, bottom_right) = Self::rect_bounds(top_left, size);
+                coord.x >= top_left.x && coord.y >= top_left.y &&
+                    coord.x < bottom_right.x && coord.y < bottom_right.y
+            }
+            Region::Chebyshev(centre, radius) => {
+                let dx = (coord.x as i64 - centre.x as i64).abs();
+                let dy = (coord.y as i64 - centre.y as i64).abs();
+                dx.max(dy) <= radius as i64
+            }
+            Region::Euclidean(centre, radius) => {
+                let dx = coord.x as i64 - centre.x as i64;
+                let dy = coord.y as i64 - centre.y as i64;
+                dx * dx + dy * dy <= radius as i64 * radius as i64
+            }
+        }
+    }
+
+    fn rect_bounds(top_left: Coord, size: Size) -> (Coord, Coord) {
+        let bottom_right = Coord::new(
+            top_left.x.saturating_add(size.width() as i32),
+            top_left.y.saturating_add(size.height() as i32),
+        );
+        (top_left, bottom_right)
+    }
+
+    /// Returns the top-left corner (inclusive) and bottom-right
+    /// corner (exclusive) of the rectangle containing the region.
+    fn bounds(&self) -> (Coord, Coord) {
+        match *self {
+            Region::Rect(top_left, size) => Self::rect_bounds(top_left, size),
+            Region::Chebyshev(centre, radius) | Region::Euclidean(centre, radius) => {
+                let radius = radius.min(i32::MAX as u32) as i32;
+                let top_left = Coord::new(centre.x.saturating_sub(radius), centre.y.saturating_sub(radius));
+                let bottom_right = Coord::new(
+                    centre.x.saturating_add(radius).saturating_add(1),
+                    centre.y.saturating_add(radius).saturating_add(1),
+                );
+                (top_left, bottom_right)
+            }
+        }
+    }
+
+    /// Iterates over the coordinates in the region which are
+    /// inside a grid of the given size, in row-major order.
+    pub fn clipped_coords(&self, size: Size) -> RegionCoordIter {
+        let (top_left, bottom_right) = self.bounds();
+        let top_left = Coord::new(top_left.x.max(0), top_left.y.max(0));
+        let bottom_right = Coord::new(
+            bottom_right.x.min(size.width() as i32),
+            bottom_right.y.min(size.height() as i32),
+        );
+        RegionCoordIter {
+            region: *self,
+            top_left,
+            bottom_right,
+            next: top_left,
+        }
+    }
+}
+
+pub struct RegionCoordIter {
+    region: Region,
+    top_left: Coord,
+    bottom_right: Coord,
+    next: Coord,
+}
+
+impl Iterator for RegionCoordIter {
+    type Item = Coord;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.top_left.x >= self.bottom_right.x {
+            return None;
+        }
+        while self.next.y < self.bottom_right.y {
+            let coord = self.next;
+            self.next.x += 1;
+            if self.next.x == self.bottom_right.x {
+                self.next.x = self.top_left.x;
+                self.next.y += 1;
+            }
+            if self.region.contains(coord) {
+                return Some(coord);
+            }
+        }
+        None
+    }
+}
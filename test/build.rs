@@ -0,0 +1,17 @@
+extern crate entity_store_code_gen;
+
+// Each variant is generated from spec.toml with some top-level keys prepended.
+const VARIANTS: &[(&str, &str)] = &[
+    ("component.rs", "layout = \"component\"\n"),
+    ("archetype.rs", "layout = \"archetype\"\n"),
+    ("component_generational.rs", "layout = \"component\"\ngenerational_ids = true\nid_width = 8\n"),
+    ("archetype_generational.rs", "layout = \"archetype\"\ngenerational_ids = true\nid_width = 8\n"),
+];
+
+fn main() {
+    println!("cargo:rerun-if-changed=spec.toml");
+    let spec = include_str!("spec.toml");
+    for &(filename, keys) in VARIANTS {
+        entity_store_code_gen::generate(&format!("{}{}", keys, spec), filename).unwrap();
+    }
+}
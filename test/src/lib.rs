@@ -0,0 +1,51 @@
+//! Code generated by entity_store_code_gen from `spec.toml`, in each
+//! layout, with and without generational ids. The tests exercising
+//! it live in the `tests` directory, and are run against each version
+//! with the `tests!` macro.
+
+#[macro_use] extern crate enum_primitive;
+#[macro_use] extern crate serde_derive;
+#[macro_use] extern crate entity_store_helper;
+
+pub mod component {
+    include_entity_store!("component.rs");
+}
+
+pub mod archetype {
+    include_entity_store!("archetype.rs");
+}
+
+pub mod component_generational {
+    include_entity_store!("component_generational.rs");
+}
+
+pub mod archetype_generational {
+    include_entity_store!("archetype_generational.rs");
+}
+
+/// Expands a body of tests once for each version of the generated code,
+/// in a module named after the version. The module imports the version's
+/// generated code and defines a `world()` fixture. The versions may be
+/// listed before `=>` to restrict the tests to them.
+#[macro_export]
+macro_rules! tests {
+    ($($layout:ident),+ => $body:tt) => {
+        $(tests!(@layout $layout $body);)+
+    };
+    (@layout $layout:ident { $($body:tt)* }) => {
+        mod $layout {
+            #[allow(unused_imports)]
+            use $crate::$layout::*;
+
+            #[allow(dead_code)]
+            fn world() -> EntityWorld {
+                EntityWorld::new(Size::new(8, 8), Size::new(8, 8), Size::new(4, 4))
+            }
+
+            $($body)*
+        }
+    };
+    ($($body:tt)*) => {
+        tests!(component, archetype, component_generational, archetype_generational => { $($body)* });
+    };
+}
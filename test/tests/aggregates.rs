@@ -0,0 +1,63 @@
+#[macro_use] extern crate entity_store_test;
+
+tests! {
+    #[test]
+    fn min_is_recomputed_after_removal() {
+        let mut world = world();
+        let coord = Coord::new(2, 2);
+        let ids = [3, 1, 1, 5].iter()
+            .map(|&tile| EntityBuilder::new().coord(coord).tile(tile).spawn(&mut world).unwrap())
+            .collect::<Vec<_>>();
+        let lowest = |world: &EntityWorld| world.spatial_hash().get(coord).unwrap().lowest_tile.get().cloned();
+        assert_eq!(lowest(&world), Some(1));
+
+        // another entity has the same value
+        world.commit(remove::tile(ids[1])).unwrap();
+        assert_eq!(lowest(&world), Some(1));
+        world.despawn(ids[2]);
+        assert_eq!(lowest(&world), Some(3));
+        world.commit(insert::tile(ids[0], 7)).unwrap();
+        assert_eq!(lowest(&world), Some(5));
+        world.commit(insert::coord(ids[3], Coord::new(3, 3))).unwrap();
+        assert_eq!(lowest(&world), Some(7));
+        assert_eq!(world.spatial_hash().get(Coord::new(3, 3)).unwrap().lowest_tile.get(), Some(&5));
+        world.commit(remove::coord(ids[0])).unwrap();
+        assert_eq!(lowest(&world), None);
+    }
+
+    #[test]
+    fn max_is_recomputed_after_removal() {
+        let mut world = world();
+        let coord = Coord::new(0, 7);
+        let ids = [4, 9, 9, -2].iter()
+            .map(|&health| EntityBuilder::new().coord(coord).health(health).spawn(&mut world).unwrap())
+            .collect::<Vec<_>>();
+        let highest = |world: &EntityWorld| world.spatial_hash().get(coord).unwrap().highest_health.get().cloned();
+        assert_eq!(highest(&world), Some(9));
+
+        world.commit(remove::health(ids[1])).unwrap();
+        assert_eq!(highest(&world), Some(9));
+        world.commit(insert::health(ids[2], 0)).unwrap();
+        assert_eq!(highest(&world), Some(4));
+        world.despawn(ids[0]);
+        assert_eq!(highest(&world), Some(0));
+        world.commit_batch(vec![remove::health(ids[2]), remove::health(ids[3])]).unwrap();
+        assert_eq!(highest(&world), None);
+    }
+
+    #[test]
+    fn any_is_cleared_with_the_last_entity() {
+        let mut world = world();
+        let coord = Coord::new(5, 1);
+        let a = EntityBuilder::new().coord(coord).door().spawn(&mut world).unwrap();
+        let b = EntityBuilder::new().coord(coord).door().spawn(&mut world).unwrap();
+        let cell = |world: &EntityWorld| world.spatial_hash().get(coord).unwrap().has_door.get();
+        assert!(cell(&world));
+
+        world.commit(remove::door(a)).unwrap();
+        assert!(cell(&world));
+        world.commit(insert::coord(b, Coord::new(5, 2))).unwrap();
+        assert!(!cell(&world));
+        assert!(world.spatial_hash().get(Coord::new(5, 2)).unwrap().has_door.get());
+    }
+}
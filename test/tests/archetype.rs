@@ -0,0 +1,112 @@
+#[macro_use] extern crate entity_store_test;
+extern crate entity_store_helper;
+
+tests! {
+    #[test]
+    fn components_survive_moving_between_archetypes() {
+        let mut world = world();
+        let ids = (0..3).map(|_| world.spawn()).collect::<Vec<_>>();
+        for (i, &id) in ids.iter().enumerate() {
+            world.commit(insert::coord(id, Coord::new(i as i32, 0))).unwrap();
+            world.commit(insert::tile(id, i as u8)).unwrap();
+        }
+
+        // moves the first entity out of the table it shares with the others
+        world.commit(insert::solid(ids[0])).unwrap();
+        world.commit(insert::health(ids[0], 3)).unwrap();
+        world.commit(remove::tile(ids[0])).unwrap();
+
+        let store = world.entity_store();
+        assert_eq!(store.get_coord(ids[0]), Some(&Coord::new(0, 0)));
+        assert_eq!(store.get_health(ids[0]), Some(&3));
+        assert!(store.contains_solid(ids[0]));
+        assert_eq!(store.get_tile(ids[0]), None);
+        for (i, &id) in ids.iter().enumerate().skip(1) {
+            assert_eq!(store.get_coord(id), Some(&Coord::new(i as i32, 0)));
+            assert_eq!(store.get_tile(id), Some(&(i as u8)));
+            assert!(!store.contains_solid(id));
+        }
+    }
+
+    #[test]
+    fn removing_every_component_leaves_others_intact() {
+        let mut world = world();
+        let a = world.spawn();
+        let b = world.spawn();
+        for &id in [a, b].iter() {
+            world.commit(insert::coord(id, Coord::new(1, 1))).unwrap();
+            world.commit(insert::velocity(id, (1, 0))).unwrap();
+        }
+        world.commit(insert::player(b)).unwrap();
+
+        world.commit(remove::velocity(a)).unwrap();
+        world.commit(remove::coord(a)).unwrap();
+        assert!(world.entity_component_table().component_types(a).next().is_none());
+        assert_eq!(world.entity_store().get_coord(a), None);
+
+        let store = world.entity_store();
+        assert_eq!(store.get_coord(b), Some(&Coord::new(1, 1)));
+        assert_eq!(store.get_velocity(b), Some(&(1, 0)));
+        assert!(store.contains_player(b));
+        let moving = store.query_moving().map(|(id, coord, velocity, _, _)| (id, *coord, *velocity))
+            .collect::<Vec<_>>();
+        assert_eq!(moving, vec![(b, Coord::new(1, 1), (1, 0))]);
+    }
+
+    #[test]
+    fn replacing_a_value_keeps_the_entity_in_place() {
+        let mut world = world();
+        let a = world.spawn();
+        let b = world.spawn();
+        world.commit(insert::tile(a, 1)).unwrap();
+        world.commit(insert::tile(b, 2)).unwrap();
+        world.commit(insert::tile(a, 3)).unwrap();
+
+        assert_eq!(world.entity_store().get_tile(a), Some(&3));
+        assert_eq!(world.entity_store().get_tile(b), Some(&2));
+    }
+}
+
+// Only the archetype layout has a single row per slot, which a
+// stale id mustn't take over from the entity now occupying it.
+mod stale_occupant {
+    tests!(archetype_generational => {
+        use entity_store_helper::GenerationalId;
+
+        #[test]
+        fn stale_ids_leave_the_occupant_in_place() {
+            let mut store = EntityStore::new();
+            let stale = GenerationalId::new(0, 0);
+            let id = GenerationalId::new(0, 1);
+            store.commit(insert::tile(id, 1)).unwrap();
+            store.commit(insert::faction(id, 2)).unwrap();
+            store.commit(insert::solid(id)).unwrap();
+
+            store.commit(insert::tile(stale, 3)).unwrap();
+            store.commit(insert::player(stale)).unwrap();
+            store.commit(remove::faction(stale)).unwrap();
+            assert_eq!(store.get_tile(id), Some(&1));
+            assert_eq!(store.get_faction(id), Some(&2));
+            assert!(store.contains_solid(id));
+            assert!(!store.contains_player(id));
+            assert_eq!(store.get_tile(stale), None);
+            assert_eq!(store.entities_with_tile(&1).collect::<Vec<_>>(), vec![id]);
+            assert_eq!(store.entities_with_tile(&3).count(), 0);
+        }
+
+        #[test]
+        fn stale_ids_leave_the_world_in_sync() {
+            let mut world = world();
+            let stale = world.spawn();
+            world.despawn(stale);
+            let id = world.spawn();
+            world.commit(insert::coord(id, Coord::new(1, 1))).unwrap();
+            world.commit(insert::solid(id)).unwrap();
+
+            world.commit(insert::tile(stale, 3)).unwrap();
+            assert_eq!(world.entity_store().get_coord(id), Some(&Coord::new(1, 1)));
+            assert!(world.entity_store().contains_solid(id));
+            assert_eq!(world.spatial_hash().get(Coord::new(1, 1)).unwrap().solid_count, 1);
+        }
+    });
+}
@@ -0,0 +1,48 @@
+#[macro_use] extern crate entity_store_test;
+
+tests! {
+    #[test]
+    fn only_set_components_are_inserted() {
+        let mut world = world();
+        let id = EntityBuilder::new()
+            .coord(Coord::new(2, 3))
+            .tile(4)
+            .solid()
+            .spawn(&mut world).unwrap();
+
+        let store = world.entity_store();
+        assert_eq!(store.get_coord(id), Some(&Coord::new(2, 3)));
+        assert_eq!(store.get_tile(id), Some(&4));
+        assert!(store.contains_solid(id));
+        assert!(!store.contains_door(id));
+        assert_eq!(store.get_health(id), None);
+        let mut component_types = world.entity_component_table().component_types(id).collect::<Vec<_>>();
+        component_types.sort_by_key(|&typ| typ as usize);
+        let mut expected = vec![ComponentType::Coord, ComponentType::Tile, ComponentType::Solid];
+        expected.sort_by_key(|&typ| typ as usize);
+        assert_eq!(component_types, expected);
+
+        let cell = world.spatial_hash().get(Coord::new(2, 3)).unwrap();
+        assert_eq!(cell.solid_count, 1);
+        assert!(cell.entities.contains(&id));
+        assert!(EntityBuilder::new().changes(id).is_empty());
+    }
+
+    #[test]
+    fn builders_can_be_reused() {
+        let mut world = world();
+        let builder = EntityBuilder::new().health(5).faction(1);
+        let a = builder.spawn(&mut world).unwrap();
+        let b = builder.clone().health(6).spawn(&mut world).unwrap();
+        let mut store = world.entity_store().clone();
+        let c = world.spawn();
+        builder.commit(c, &mut store).unwrap();
+
+        assert_ne!(a, b);
+        assert_eq!(world.entity_store().get_health(a), Some(&5));
+        assert_eq!(world.entity_store().get_health(b), Some(&6));
+        assert_eq!(store.get_health(c), Some(&5));
+        assert_eq!(store.get_faction(c), Some(&1));
+        assert_eq!(world.entity_store().entities_with_faction(&1).count(), 2);
+    }
+}
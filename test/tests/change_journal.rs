@@ -0,0 +1,116 @@
+#[macro_use] extern crate entity_store_test;
+
+tests! {
+    #[test]
+    fn undo_and_redo_whole_transactions() {
+        let mut world = world();
+        let mut journal = ChangeJournal::new();
+        let id = world.spawn();
+        journal.commit(&mut world, insert::coord(id, Coord::new(1, 1))).unwrap();
+        journal.commit(&mut world, insert::solid(id)).unwrap();
+        journal.end_transaction();
+        journal.commit(&mut world, insert::coord(id, Coord::new(2, 2))).unwrap();
+        journal.commit(&mut world, insert::tile(id, 3)).unwrap();
+        journal.end_transaction();
+
+        assert!(journal.undo(&mut world));
+        assert_eq!(world.entity_store().get_coord(id), Some(&Coord::new(1, 1)));
+        assert_eq!(world.entity_store().get_tile(id), None);
+        assert!(world.entity_store().contains_solid(id));
+        assert_eq!(world.spatial_hash().get(Coord::new(1, 1)).unwrap().solid_count, 1);
+        assert_eq!(world.spatial_hash().get(Coord::new(2, 2)).unwrap().solid_count, 0);
+
+        assert!(journal.undo(&mut world));
+        assert!(world.entity_component_table().component_types(id).next().is_none());
+        assert_eq!(world.spatial_hash().get(Coord::new(1, 1)).unwrap().solid_count, 0);
+        assert!(!journal.can_undo());
+        assert!(!journal.undo(&mut world));
+
+        assert!(journal.redo(&mut world));
+        assert!(journal.redo(&mut world));
+        assert!(!journal.can_redo());
+        assert!(!journal.redo(&mut world));
+        assert_eq!(world.entity_store().get_coord(id), Some(&Coord::new(2, 2)));
+        assert_eq!(world.entity_store().get_tile(id), Some(&3));
+        assert_eq!(world.spatial_hash().get(Coord::new(2, 2)).unwrap().solid_count, 1);
+    }
+
+    #[test]
+    fn undo_ends_the_current_transaction() {
+        let mut world = world();
+        let mut journal = ChangeJournal::new();
+        let id = world.spawn();
+        journal.commit(&mut world, insert::health(id, 1)).unwrap();
+        journal.commit(&mut world, insert::health(id, 2)).unwrap();
+        assert!(journal.can_undo());
+        assert!(journal.undo(&mut world));
+        assert_eq!(world.entity_store().get_health(id), None);
+    }
+
+    #[test]
+    fn committing_discards_redo() {
+        let mut world = world();
+        let mut journal = ChangeJournal::new();
+        let id = world.spawn();
+        journal.commit(&mut world, insert::tile(id, 1)).unwrap();
+        journal.undo(&mut world);
+        assert!(journal.can_redo());
+
+        journal.commit(&mut world, insert::tile(id, 2)).unwrap();
+        assert!(!journal.can_redo());
+        journal.clear();
+        assert!(!journal.can_undo());
+        assert_eq!(world.entity_store().get_tile(id), Some(&2));
+    }
+
+    #[test]
+    fn removals_and_resources_are_restored() {
+        let mut world = world();
+        let mut journal = ChangeJournal::new();
+        let id = EntityBuilder::new().tile(4).faction(2).spawn(&mut world).unwrap();
+        world.commit(set::turn(1)).unwrap();
+        world.commit(set::seed(7)).unwrap();
+
+        journal.commit(&mut world, remove::tile(id)).unwrap();
+        journal.commit(&mut world, set::turn(2)).unwrap();
+        journal.commit(&mut world, clear::seed()).unwrap();
+        journal.undo(&mut world);
+        assert_eq!(world.entity_store().get_tile(id), Some(&4));
+        assert_eq!(world.entity_store().entities_with_tile(&4).collect::<Vec<_>>(), vec![id]);
+        assert_eq!(world.entity_store().turn(), Some(&1));
+        assert_eq!(world.entity_store().seed(), Some(&7));
+    }
+
+    #[test]
+    fn replaced_unique_owners_are_restored() {
+        let mut world = world();
+        let mut journal = ChangeJournal::new();
+        let a = world.spawn();
+        let b = world.spawn();
+        journal.commit(&mut world, insert::leader(a)).unwrap();
+        journal.end_transaction();
+        journal.commit(&mut world, insert::leader(b)).unwrap();
+        assert_eq!(world.entity_store().get_leader_entity(), Some(b));
+
+        journal.undo(&mut world);
+        assert_eq!(world.entity_store().get_leader_entity(), Some(a));
+        assert!(world.entity_store().contains_leader(a));
+        assert!(!world.entity_store().contains_leader(b));
+        journal.redo(&mut world);
+        assert_eq!(world.entity_store().get_leader_entity(), Some(b));
+        assert!(!world.entity_store().contains_leader(a));
+    }
+
+    #[test]
+    fn inverse_of_a_change_without_effect_is_none() {
+        let mut world = world();
+        let id = world.spawn();
+        assert!(inverse_change(world.entity_store(), &remove::tile(id)).is_none());
+        assert!(inverse_change(world.entity_store(), &clear::turn()).is_none());
+        world.commit(insert::tile(id, 1)).unwrap();
+        match inverse_change(world.entity_store(), &insert::tile(id, 2)) {
+            Some(EntityChange::Insert(inverse_id, ComponentValue::Tile(1))) => assert_eq!(inverse_id, id),
+            other => panic!("unexpected inverse: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,62 @@
+#[macro_use] extern crate entity_store_test;
+
+tests! {
+    #[test]
+    fn components_are_copied() {
+        let mut world = world();
+        let parent = world.spawn();
+        let source = EntityBuilder::new()
+            .coord(Coord::new(1, 1))
+            .tile(3)
+            .faction(2)
+            .solid()
+            .contained_in(parent)
+            .spawn(&mut world).unwrap();
+
+        let copy = world.clone_entity(source);
+        assert_ne!(copy, source);
+        let store = world.entity_store();
+        for &id in [source, copy].iter() {
+            assert_eq!(store.get_coord(id), Some(&Coord::new(1, 1)));
+            assert_eq!(store.get_tile(id), Some(&3));
+            assert_eq!(store.get_faction(id), Some(&2));
+            assert!(store.contains_solid(id));
+            assert_eq!(store.get_contained_in(id), Some(&parent));
+        }
+        assert_eq!(store.children_contained_in(parent), &[source, copy]);
+        assert_eq!(store.entities_with_faction(&2).count(), 2);
+        let cell = world.spatial_hash().get(Coord::new(1, 1)).unwrap();
+        assert_eq!(cell.solid_count, 2);
+        assert!(cell.entities.contains(&copy));
+
+        // the copy is independent of its source
+        world.commit(insert::tile(copy, 4)).unwrap();
+        world.despawn(source);
+        assert_eq!(world.entity_store().get_tile(copy), Some(&4));
+        assert_eq!(world.spatial_hash().get(Coord::new(1, 1)).unwrap().solid_count, 1);
+    }
+
+    #[test]
+    fn unique_components_are_not_copied() {
+        let mut world = world();
+        let source = EntityBuilder::new().leader().target(1).health(2).spawn(&mut world).unwrap();
+        let copy = world.clone_entity(source);
+
+        let store = world.entity_store();
+        assert_eq!(store.get_health(copy), Some(&2));
+        assert!(!store.contains_leader(copy));
+        assert_eq!(store.get_target(copy), None);
+        assert_eq!(store.get_leader_entity(), Some(source));
+        assert_eq!(store.get_target_entity(), Some(source));
+    }
+
+    #[test]
+    fn cloning_an_empty_entity_allocates_a_new_id() {
+        let mut world = world();
+        let source = world.spawn();
+        let copy = world.clone_entity(source);
+        assert_ne!(copy, source);
+        assert!(world.entity_component_table().component_types(copy).next().is_none());
+        assert!(world.check_commit(&insert::solid(copy)).is_ok());
+    }
+}
@@ -0,0 +1,114 @@
+#[macro_use] extern crate entity_store_test;
+
+tests! {
+    fn sorted(ids: &::std::collections::HashSet<EntityId>) -> Vec<EntityId> {
+        let mut ids = ids.iter().cloned().collect::<Vec<_>>();
+        ids.sort();
+        ids
+    }
+
+    // Describes everything derived from the store, ignoring
+    // the times at which cells were last updated.
+    fn summary(world: &EntityWorld, ids: &[EntityId]) -> Vec<String> {
+        let mut summary = Vec::new();
+        for &id in ids {
+            for component_type in world.entity_component_table().component_types(id) {
+                summary.push(format!("{:?} {:?}", id, world.entity_store().get(id, component_type).map(|r| r.to_value())));
+            }
+        }
+        for (coord, cell) in world.spatial_hash().enumerate() {
+            summary.push(format!("{:?} {} {} {} {:?} {:?} {:?} {} {:?} {:?} {:?} {:?}",
+                                 coord, cell.solid_count, cell.leader_count, cell.opacity_total, cell.lowest_tile.get(),
+                                 cell.highest_health.get(), cell.has_door.get(), cell.flags.bits(),
+                                 sorted(&cell.entities), cell.solid_neighbours, cell.door_neighbours,
+                                 cell.health_neighbours));
+        }
+        for (coord, cell) in world.spatial_hash_items().enumerate() {
+            summary.push(format!("{:?} {} {:?} {:?}",
+                                 coord, cell.health_total, sorted(&cell.tiles), cell.health_around));
+        }
+        for (coord, cell) in world.spatial_hash_regions().enumerate() {
+            summary.push(format!("{:?} {}", coord, cell.solid_count));
+        }
+        summary
+    }
+
+    fn changes(ids: &[EntityId]) -> Vec<EntityChange> {
+        vec![
+            insert::coord(ids[0], Coord::new(1, 1)),
+            insert::solid(ids[0]),
+            insert::tile(ids[0], 3),
+            insert::health(ids[0], 5),
+            insert::region(ids[0], Coord::new(0, 0)),
+            insert::coord(ids[1], Coord::new(2, 1)),
+            insert::door(ids[1]),
+            insert::tile(ids[1], 1),
+            insert::health(ids[1], 9),
+            insert::opacity(ids[1], 0.5),
+            insert::player(ids[1]),
+            insert::region(ids[1], Coord::new(0, 0)),
+            insert::solid(ids[1]),
+            insert::coord(ids[2], Coord::new(1, 1)),
+            insert::solid(ids[2]),
+            insert::health(ids[2], 2),
+            insert::coord(ids[2], Coord::new(3, 3)),
+            insert::leader(ids[0]),
+            insert::leader(ids[1]),
+            insert::target(ids[2], 1),
+            insert::target(ids[0], 2),
+            remove::solid(ids[0]),
+            insert::tile(ids[0], 6),
+            remove::coord(ids[1]),
+            remove::region(ids[0]),
+            insert::region(ids[0], Coord::new(1, 1)),
+            set::turn(4),
+        ]
+    }
+
+    #[test]
+    fn commit_batch_matches_commit() {
+        let mut one_at_a_time = world();
+        let mut batched = world();
+        let ids = (0..3).map(|_| one_at_a_time.spawn()).collect::<Vec<_>>();
+        for &id in ids.iter() {
+            assert_eq!(batched.spawn(), id);
+        }
+
+        // the second target is rejected by both
+        for change in changes(&ids) {
+            let _ = one_at_a_time.commit(change);
+        }
+        assert!(batched.commit_batch(changes(&ids)).is_err());
+
+        assert_eq!(summary(&one_at_a_time, &ids), summary(&batched, &ids));
+        assert_eq!(batched.entity_store().get_leader_entity(), Some(ids[1]));
+        assert_eq!(batched.entity_store().get_target_entity(), Some(ids[2]));
+        assert_eq!(batched.entity_store().turn(), Some(&4));
+    }
+
+    #[test]
+    fn commit_batch_matches_commit_when_removing() {
+        let mut one_at_a_time = world();
+        let mut batched = world();
+        let ids = (0..3).map(|_| one_at_a_time.spawn()).collect::<Vec<_>>();
+        for &id in ids.iter() {
+            assert_eq!(batched.spawn(), id);
+        }
+        for change in changes(&ids) {
+            let _ = one_at_a_time.commit(change.clone());
+            let _ = batched.commit(change);
+        }
+
+        let removals = ids.iter().flat_map(|&id| {
+            one_at_a_time.entity_component_table().component_types(id)
+                .map(move |component_type| EntityChange::Remove(id, component_type))
+        }).collect::<Vec<_>>();
+        for change in removals.iter().cloned() {
+            one_at_a_time.commit(change).unwrap();
+        }
+        batched.commit_batch(removals).unwrap();
+
+        assert_eq!(summary(&one_at_a_time, &ids), summary(&batched, &ids));
+        assert_eq!(batched.spatial_hash().get(Coord::new(3, 3)).unwrap().solid_count, 0);
+    }
+}
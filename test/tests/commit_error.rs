@@ -0,0 +1,116 @@
+#[macro_use] extern crate entity_store_test;
+
+tests! {
+    #[test]
+    fn unstored_components_are_reported() {
+        let mut world = world();
+        let id = world.spawn();
+        // components without a storage type are only stored in the archetype layout
+        let sound_stored = module_path!().contains("archetype");
+        assert_eq!(ComponentType::Sound.is_stored(), sound_stored);
+        match world.try_commit(insert::noise(id, 1)) {
+            Err(CommitError::NotStored(rejected, ComponentType::Sound)) => {
+                assert!(!sound_stored);
+                assert_eq!(rejected, id);
+            }
+            Ok(()) => assert!(sound_stored),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn absent_components_are_reported() {
+        let mut world = world();
+        let id = world.spawn();
+        match world.try_commit(remove::tile(id)) {
+            Err(CommitError::AbsentComponent(rejected, ComponentType::Tile)) => assert_eq!(rejected, id),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        world.commit(insert::tile(id, 1)).unwrap();
+        assert!(world.try_commit(remove::tile(id)).is_ok());
+        assert_eq!(world.entity_store().get_tile(id), None);
+    }
+
+    #[test]
+    fn unallocated_ids_are_reported() {
+        let mut world = world();
+        let id = world.spawn();
+        let unallocated = world.spawn();
+        world.despawn(unallocated);
+        match world.try_commit(insert::tile(unallocated, 1)) {
+            Err(CommitError::UnallocatedId(rejected)) => assert_eq!(rejected, unallocated),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert!(world.try_commit(insert::tile(id, 1)).is_ok());
+    }
+
+    #[test]
+    fn positions_outside_grids_are_reported() {
+        let mut world = world();
+        let id = world.spawn();
+        for change in [
+            insert::coord(id, Coord::new(8, 0)),
+            insert::coord(id, Coord::new(0, -1)),
+            insert::region(id, Coord::new(4, 4)),
+        ].iter().cloned() {
+            match world.try_commit(change) {
+                Err(CommitError::PositionOutsideGrid(rejected, _)) => assert_eq!(rejected, id),
+                other => panic!("unexpected result: {:?}", other),
+            }
+        }
+        assert_eq!(world.entity_store().get_coord(id), None);
+        assert_eq!(world.entity_store().get_region(id), None);
+        assert!(world.try_commit(insert::coord(id, Coord::new(7, 7))).is_ok());
+        assert!(world.try_commit(insert::region(id, Coord::new(3, 3))).is_ok());
+    }
+
+    #[test]
+    fn absent_resources_are_reported() {
+        let mut world = world();
+        match world.try_commit(clear::turn()) {
+            Err(CommitError::AbsentResource(ResourceType::Turn)) => (),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert!(world.try_commit(set::turn(1)).is_ok());
+        assert!(world.try_commit(clear::turn()).is_ok());
+        assert_eq!(world.entity_store().turn(), None);
+    }
+
+    #[test]
+    fn batches_are_checked_in_order() {
+        let mut world = world();
+        let id = world.spawn();
+        assert!(world.check_commit_batch(&[insert::tile(id, 1), remove::tile(id)]).is_ok());
+        assert!(world.check_commit_batch(&[set::turn(1), clear::turn()]).is_ok());
+        match world.check_commit_batch(&[insert::tile(id, 1), remove::tile(id), remove::tile(id)]) {
+            Err(CommitError::AbsentComponent(_, ComponentType::Tile)) => (),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match world.check_commit_batch(&[set::turn(1), clear::turn(), clear::turn()]) {
+            Err(CommitError::AbsentResource(ResourceType::Turn)) => (),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn failed_batches_leave_world_unchanged() {
+        let mut world = world();
+        let id = world.spawn();
+        let result = world.try_commit_batch(vec![
+            insert::coord(id, Coord::new(1, 1)),
+            insert::solid(id),
+            set::turn(1),
+            remove::tile(id),
+        ]);
+        match result {
+            Err(CommitError::AbsentComponent(_, ComponentType::Tile)) => (),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert!(world.entity_component_table().component_types(id).next().is_none());
+        assert_eq!(world.entity_store().turn(), None);
+        assert_eq!(world.spatial_hash().get(Coord::new(1, 1)).unwrap().solid_count, 0);
+
+        assert!(world.try_commit_batch(vec![insert::coord(id, Coord::new(1, 1)), insert::solid(id)]).is_ok());
+        assert_eq!(world.spatial_hash().get(Coord::new(1, 1)).unwrap().solid_count, 1);
+    }
+}
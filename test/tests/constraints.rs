@@ -0,0 +1,72 @@
+#[macro_use] extern crate entity_store_test;
+
+tests! {
+    #[test]
+    fn missing_requirements_are_reported() {
+        let mut world = world();
+        let id = world.spawn();
+        match world.try_commit(insert::velocity(id, (1, 0))) {
+            Err(CommitError::ConstraintViolation(violation)) => {
+                assert_eq!(violation, ConstraintViolation::Requires(id, ComponentType::Velocity, ComponentType::Coord));
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert_eq!(world.entity_store().get_velocity(id), None);
+
+        world.commit(insert::coord(id, Coord::new(0, 0))).unwrap();
+        assert!(world.try_commit(insert::velocity(id, (1, 0))).is_ok());
+        match world.check_commit(&remove::coord(id)) {
+            Err(CommitError::ConstraintViolation(ConstraintViolation::Requires(..))) => (),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn exclusions_are_reported() {
+        let mut world = world();
+        let id = world.spawn();
+        world.commit(insert::solid(id)).unwrap();
+        match world.try_commit(insert::door(id)) {
+            Err(CommitError::ConstraintViolation(violation)) => {
+                assert_eq!(violation, ConstraintViolation::Excludes(id, ComponentType::Door, ComponentType::Solid));
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert!(!world.entity_store().contains_door(id));
+
+        // the exclusion applies in both directions
+        world.commit(remove::solid(id)).unwrap();
+        world.commit(insert::door(id)).unwrap();
+        assert!(world.check_commit(&insert::solid(id)).is_err());
+    }
+
+    #[test]
+    fn batches_are_checked_once_complete() {
+        let mut world = world();
+        let id = world.spawn();
+        assert!(world.try_commit_batch(vec![insert::velocity(id, (0, 1)), insert::coord(id, Coord::new(0, 0))]).is_ok());
+        assert_eq!(world.entity_store().get_velocity(id), Some(&(0, 1)));
+
+        assert!(world.check_commit_batch(&[remove::coord(id), remove::velocity(id)]).is_ok());
+        assert!(world.check_commit_batch(&[insert::solid(id), insert::door(id), remove::solid(id)]).is_ok());
+        assert!(world.check_commit_batch(&[insert::solid(id), insert::door(id)]).is_err());
+    }
+
+    #[test]
+    fn unchecked_commits_are_found_afterwards() {
+        let mut world = world();
+        let a = world.spawn();
+        let b = world.spawn();
+        world.commit(insert::solid(a)).unwrap();
+        world.commit(insert::door(b)).unwrap();
+        assert!(world.entity_component_table().check_constraints().is_ok());
+
+        // commit doesn't check constraints
+        world.commit(insert::solid(b)).unwrap();
+        assert_eq!(world.entity_component_table().check_entity_constraints(a), Ok(()));
+        assert_eq!(
+            world.entity_component_table().check_constraints(),
+            Err(ConstraintViolation::Excludes(b, ComponentType::Door, ComponentType::Solid))
+        );
+    }
+}
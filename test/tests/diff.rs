@@ -0,0 +1,85 @@
+#[macro_use] extern crate entity_store_test;
+
+tests! {
+    fn sorted(changes: Vec<EntityChange>) -> Vec<String> {
+        let mut changes = changes.iter().map(|change| format!("{:?}", change)).collect::<Vec<_>>();
+        changes.sort();
+        changes
+    }
+
+    fn contents(entity_store: &EntityStore) -> Vec<String> {
+        let mut changes = Vec::new();
+        entity_store.clone_changes(&mut changes);
+        sorted(changes)
+    }
+
+    #[test]
+    fn equal_values_are_skipped_when_compared() {
+        let mut world = world();
+        let id = EntityBuilder::new().tile(1).velocity((1, 0)).coord(Coord::new(0, 0)).health(3).solid().spawn(&mut world).unwrap();
+        world.commit(set::turn(2)).unwrap();
+        let store = world.entity_store();
+        assert!(store.diff(&store.clone()).iter().all(|change| change.typ() == ComponentType::Coord));
+
+        let mut other = store.clone();
+        other.commit(insert::tile(id, 1)).unwrap();
+        other.commit(insert::health(id, 4)).unwrap();
+        assert_eq!(sorted(store.diff(&other).into_iter().filter(|change| change.typ() != ComponentType::Coord).collect()),
+                   sorted(vec![insert::health(id, 4)]));
+    }
+
+    #[test]
+    fn differences_become_inserts_and_removes() {
+        let mut world = world();
+        let a = EntityBuilder::new().tile(1).solid().faction(2).spawn(&mut world).unwrap();
+        let b = EntityBuilder::new().tile(2).player().spawn(&mut world).unwrap();
+        world.commit(set::turn(1)).unwrap();
+        world.commit(set::seed(5)).unwrap();
+        let before = world.entity_store().clone();
+
+        world.commit(remove::solid(a)).unwrap();
+        world.commit(insert::door(a)).unwrap();
+        world.commit(remove::tile(b)).unwrap();
+        world.commit(insert::tile(a, 3)).unwrap();
+        world.commit(set::turn(2)).unwrap();
+        world.commit(clear::seed()).unwrap();
+        let after = world.entity_store();
+
+        assert_eq!(sorted(before.diff(after)), sorted(vec![
+            insert::door(a),
+            remove::solid(a),
+            insert::tile(a, 3),
+            remove::tile(b),
+            insert::faction(a, 2),
+            set::turn(2),
+            clear::seed(),
+        ]));
+    }
+
+    #[test]
+    fn committing_a_diff_reproduces_the_other_store() {
+        let mut world = world();
+        let a = EntityBuilder::new().coord(Coord::new(1, 1)).velocity((0, 1)).health(2).spawn(&mut world).unwrap();
+        let b = EntityBuilder::new().coord(Coord::new(2, 2)).solid().leader().spawn(&mut world).unwrap();
+        let before = world.entity_store().clone();
+
+        world.commit(insert::leader(a)).unwrap();
+        world.commit(insert::coord(b, Coord::new(3, 3))).unwrap();
+        world.commit(remove::velocity(a)).unwrap();
+        world.commit(insert::opacity(b, 0.5)).unwrap();
+        world.commit(set::spawn_point(Coord::new(4, 4))).unwrap();
+
+        let mut restored = before.clone();
+        for change in before.diff(world.entity_store()) {
+            restored.commit(change).unwrap();
+        }
+        assert_eq!(contents(&restored), contents(world.entity_store()));
+        assert_eq!(restored.get_leader_entity(), Some(a));
+
+        let mut reverted = world.entity_store().clone();
+        for change in world.entity_store().diff(&before) {
+            reverted.commit(change).unwrap();
+        }
+        assert_eq!(contents(&reverted), contents(&before));
+    }
+}
@@ -0,0 +1,56 @@
+#[macro_use] extern crate entity_store_test;
+
+tests! {
+    // bits follow the order of the components in the spec
+    const SOLID: usize = 0;
+    const DOOR: usize = 1;
+    const PLAYER: usize = 2;
+
+    #[test]
+    fn bits_follow_components() {
+        let mut world = world();
+        let coord = Coord::new(3, 6);
+        let solid = EntityBuilder::new().coord(coord).solid().spawn(&mut world).unwrap();
+        let player = EntityBuilder::new().coord(coord).player().spawn(&mut world).unwrap();
+        {
+            let cell = world.spatial_hash().get(coord).unwrap();
+            assert_eq!(cell.flags.bits(), 1 << SOLID | 1 << PLAYER);
+            assert!(cell.has_solid() && cell.has_player() && !cell.has_door());
+            assert!(!cell.flags.has(DOOR));
+        }
+
+        world.commit(remove::solid(solid)).unwrap();
+        world.commit(insert::door(solid)).unwrap();
+        let cell = world.spatial_hash().get(coord).unwrap();
+        assert_eq!(cell.flags.bits(), 1 << DOOR | 1 << PLAYER);
+        assert!(!cell.has_solid() && cell.has_door());
+
+        world.despawn(player);
+        world.commit(remove::coord(solid)).unwrap();
+        assert_eq!(world.spatial_hash().get(coord).unwrap().flags.bits(), 0);
+    }
+
+    #[test]
+    fn bits_stay_set_until_the_last_entity_leaves() {
+        let mut world = world();
+        let coord = Coord::new(0, 0);
+        let ids = (0..3).map(|_| EntityBuilder::new().coord(coord).solid().spawn(&mut world).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(world.spatial_hash().get(coord).unwrap().flags.count(SOLID), 3);
+
+        world.commit(remove::solid(ids[0])).unwrap();
+        world.commit(insert::coord(ids[1], Coord::new(1, 0))).unwrap();
+        {
+            let cell = world.spatial_hash().get(coord).unwrap();
+            assert_eq!(cell.flags.count(SOLID), 1);
+            assert!(cell.has_solid());
+            assert!(world.spatial_hash().get(Coord::new(1, 0)).unwrap().has_solid());
+        }
+
+        world.despawn(ids[2]);
+        let cell = world.spatial_hash().get(coord).unwrap();
+        assert_eq!(cell.flags.count(SOLID), 0);
+        assert_eq!(cell.flags.bits(), 0);
+        assert_eq!(cell.flags.count(PLAYER), 0);
+    }
+}
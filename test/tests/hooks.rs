@@ -0,0 +1,93 @@
+#[macro_use] extern crate entity_store_test;
+
+tests! {
+    use std::cell::RefCell;
+
+    #[test]
+    fn insert_hooks_receive_new_and_previous_values() {
+        let mut world = world();
+        let id = world.spawn();
+        let log = RefCell::new(Vec::new());
+        {
+            let mut hooks = EntityHooks::new();
+            hooks.on_insert_tile(|id, tile, previous| log.borrow_mut().push((id, *tile, previous.cloned())));
+            world.commit_with_hooks(insert::tile(id, 1), &mut hooks).unwrap();
+            world.commit_with_hooks(insert::tile(id, 2), &mut hooks).unwrap();
+            world.commit_with_hooks(insert::health(id, 3), &mut hooks).unwrap();
+        }
+        assert_eq!(*log.borrow(), vec![(id, 1, None), (id, 2, Some(1))]);
+        assert_eq!(world.entity_store().get_tile(id), Some(&2));
+    }
+
+    #[test]
+    fn remove_hooks_receive_removed_values() {
+        let mut world = world();
+        let id = EntityBuilder::new().health(4).solid().spawn(&mut world).unwrap();
+        let removed = RefCell::new(Vec::new());
+        let flags = RefCell::new(Vec::new());
+        {
+            let mut hooks = EntityHooks::new();
+            hooks.on_remove_health(|id, health| removed.borrow_mut().push((id, *health)));
+            hooks.on_remove_solid(|id| flags.borrow_mut().push(id));
+            world.commit_with_hooks(remove::health(id), &mut hooks).unwrap();
+            world.commit_with_hooks(remove::health(id), &mut hooks).unwrap();
+            world.commit_with_hooks(remove::solid(id), &mut hooks).unwrap();
+        }
+        assert_eq!(*removed.borrow(), vec![(id, 4)]);
+        assert_eq!(*flags.borrow(), vec![id]);
+        assert!(world.entity_store().get_health(id).is_none());
+    }
+
+    #[test]
+    fn flag_hooks_report_whether_the_flag_was_present() {
+        let mut world = world();
+        let id = world.spawn();
+        let log = RefCell::new(Vec::new());
+        {
+            let mut hooks = EntityHooks::new();
+            hooks.on_insert_door(|id, existed| log.borrow_mut().push((id, existed)));
+            hooks.on_insert_door(|_, _| log.borrow_mut().push((id, true)));
+            world.commit_with_hooks(insert::door(id), &mut hooks).unwrap();
+            world.commit_with_hooks(insert::door(id), &mut hooks).unwrap();
+        }
+        // every hook registered for a component is invoked in order
+        assert_eq!(*log.borrow(), vec![(id, false), (id, true), (id, true), (id, true)]);
+    }
+
+    #[test]
+    fn replaced_unique_owners_invoke_remove_hooks() {
+        let mut world = world();
+        let a = EntityBuilder::new().leader().spawn(&mut world).unwrap();
+        let b = world.spawn();
+        let log = RefCell::new(Vec::new());
+        let mut store = world.entity_store().clone();
+        for store_level in [false, true].iter() {
+            let mut hooks = EntityHooks::new();
+            hooks.on_remove_leader(|id| log.borrow_mut().push(("remove", id)));
+            hooks.on_insert_leader(|id, _| log.borrow_mut().push(("insert", id)));
+            if *store_level {
+                store.commit_with_hooks(insert::leader(b), &mut hooks).unwrap();
+            } else {
+                world.commit_with_hooks(insert::leader(b), &mut hooks).unwrap();
+            }
+        }
+        assert_eq!(*log.borrow(), vec![("remove", a), ("insert", b), ("remove", a), ("insert", b)]);
+        assert_eq!(store.get_leader_entity(), Some(b));
+        assert!(!world.entity_component_table().get(a).contains(ComponentType::Leader));
+    }
+
+    #[test]
+    fn rejected_changes_invoke_no_hooks() {
+        let mut world = world();
+        let a = EntityBuilder::new().target(1).spawn(&mut world).unwrap();
+        let b = world.spawn();
+        let log = RefCell::new(Vec::new());
+        {
+            let mut hooks = EntityHooks::new();
+            hooks.on_insert_target(|id, _, _| log.borrow_mut().push(id));
+            assert!(world.commit_with_hooks(insert::target(b, 2), &mut hooks).is_err());
+            world.commit_with_hooks(insert::target(a, 3), &mut hooks).unwrap();
+        }
+        assert_eq!(*log.borrow(), vec![a]);
+    }
+}
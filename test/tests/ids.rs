@@ -0,0 +1,183 @@
+#[macro_use] extern crate entity_store_test;
+extern crate entity_store_helper;
+extern crate serde_json;
+
+tests! {
+    #[test]
+    fn despawning_twice_frees_once() {
+        let mut world = world();
+        let id = world.spawn();
+        world.despawn(id);
+        world.despawn(id);
+        let a = world.spawn();
+        let b = world.spawn();
+        assert!(a != b);
+    }
+}
+
+// Stale ids can only be told apart from current ones by their generation.
+mod generational {
+    tests!(component_generational, archetype_generational => {
+        #[test]
+        fn reused_slot_gets_new_generation() {
+            let mut world = world();
+            let stale = world.spawn();
+            world.despawn(stale);
+            let id = world.spawn();
+            assert_eq!(id.index(), stale.index());
+            assert!(id != stale);
+        }
+
+        #[test]
+        fn stale_ids_are_rejected() {
+            let mut world = world();
+            let stale = world.spawn();
+            world.despawn(stale);
+            let id = world.spawn();
+
+            match world.check_commit(&insert::solid(stale)) {
+                Err(CommitError::UnallocatedId(rejected)) => assert_eq!(rejected, stale),
+                other => panic!("unexpected result: {:?}", other),
+            }
+            assert!(world.try_commit(insert::solid(stale)).is_err());
+            assert!(!world.entity_store().contains_solid(id));
+
+            world.commit(insert::tile(id, 4)).unwrap();
+            assert_eq!(world.entity_store().get_tile(stale), None);
+            assert_eq!(world.entity_store().get_tile(id), Some(&4));
+        }
+
+        #[test]
+        fn committing_through_stale_id_leaves_current_entity() {
+            let mut world = world();
+            let stale = world.spawn();
+            world.despawn(stale);
+            let id = world.spawn();
+            world.commit(insert::faction(id, 1)).unwrap();
+            world.commit(insert::health(id, 2)).unwrap();
+            world.commit(insert::solid(id)).unwrap();
+            world.commit(insert::coord(id, Coord::new(1, 1))).unwrap();
+
+            world.commit(insert::faction(stale, 9)).unwrap();
+            world.commit(remove::health(stale)).unwrap();
+            world.commit_batch(vec![insert::solid(stale), insert::tile(stale, 3), remove::coord(stale)]).unwrap();
+            let mut hooks = EntityHooks::new();
+            world.commit_with_hooks(insert::player(stale), &mut hooks).unwrap();
+
+            let store = world.entity_store();
+            assert_eq!(store.get_faction(id), Some(&1));
+            assert_eq!(store.get_health(id), Some(&2));
+            assert!(store.contains_solid(id));
+            assert_eq!(store.get_coord(id), Some(&Coord::new(1, 1)));
+            assert_eq!(store.get_tile(id), None);
+            assert!(!store.contains_player(id));
+            assert_eq!(store.entities_with_faction(&9).count(), 0);
+            assert_eq!(world.entity_component_table().component_types(id).count(), 4);
+            assert_eq!(world.spatial_hash().get(Coord::new(1, 1)).unwrap().solid_count, 1);
+
+            // the store ignores inserts through stale ids by itself
+            let mut store = world.entity_store().clone();
+            store.commit(insert::faction(stale, 9)).unwrap();
+            store.commit(insert::health(stale, 9)).unwrap();
+            store.commit(insert::solid(stale)).unwrap();
+            store.commit(insert::player(stale)).unwrap();
+            assert_eq!(store.get_faction(id), Some(&1));
+            assert_eq!(store.get_health(id), Some(&2));
+            assert!(store.contains_solid(id));
+            assert!(!store.contains_player(id));
+            assert_eq!(store.get_faction(stale), None);
+        }
+
+        #[test]
+        fn despawning_stale_id_leaves_current_entity() {
+            let mut world = world();
+            let stale = world.spawn();
+            world.despawn(stale);
+            let id = world.spawn();
+            world.commit(insert::solid(id)).unwrap();
+            world.commit(insert::coord(id, Coord::new(1, 1))).unwrap();
+
+            world.despawn(stale);
+            assert!(world.entity_store().contains_solid(id));
+            assert_eq!(world.spatial_hash().get(Coord::new(1, 1)).unwrap().solid_count, 1);
+
+            // the stale despawn must not have freed the current id's slot
+            let other = world.spawn();
+            assert!(other.index() != id.index());
+        }
+
+        #[test]
+        fn generation_wraps_around() {
+            let mut world = world();
+            let first = world.spawn();
+            let mut id = first;
+            for _ in 0..255 {
+                world.despawn(id);
+                id = world.spawn();
+            }
+            assert_eq!(id.index(), first.index());
+            assert_eq!(id.generation(), 255);
+
+            world.commit(insert::coord(id, Coord::new(2, 3))).unwrap();
+            world.commit(insert::player(id)).unwrap();
+            assert_eq!(world.entity_store().get_coord(id), Some(&Coord::new(2, 3)));
+            let last = id;
+            world.despawn(last);
+
+            let wrapped = world.spawn();
+            assert_eq!(wrapped, first);
+            assert!(world.check_commit(&insert::solid(last)).is_err());
+            assert!(world.check_commit(&insert::solid(wrapped)).is_ok());
+
+            world.commit(insert::coord(wrapped, Coord::new(4, 5))).unwrap();
+            world.commit(insert::health(wrapped, 7)).unwrap();
+            world.commit(insert::player(wrapped)).unwrap();
+            let store = world.entity_store();
+            assert_eq!(store.get_coord(wrapped), Some(&Coord::new(4, 5)));
+            assert_eq!(store.get_health(wrapped), Some(&7));
+            assert!(store.contains_player(wrapped));
+            assert_eq!(store.get_coord(last), None);
+            assert_eq!(store.get_health(last), None);
+            assert!(!store.contains_player(last));
+        }
+    });
+}
+
+// Allocators serialized before slots were tracked have only the next
+// id and the free list, from which the allocated ids are recovered.
+mod serialized {
+    use entity_store_helper::IdAllocator;
+    use serde_json;
+
+    #[test]
+    fn allocators_without_slots_can_be_loaded() {
+        let mut allocator: IdAllocator<u64> = serde_json::from_str(r#"{"next": 4, "free_list": [2, 0]}"#).unwrap();
+        assert!(!allocator.is_allocated(0));
+        assert!(allocator.is_allocated(1));
+        assert!(!allocator.is_allocated(2));
+        assert!(allocator.is_allocated(3));
+        assert!(!allocator.is_allocated(4));
+
+        // freed ids are reused in the same order as before
+        assert_eq!(allocator.allocate(), 0);
+        assert_eq!(allocator.allocate(), 2);
+        assert_eq!(allocator.allocate(), 4);
+        allocator.free(1);
+        allocator.free(1);
+        assert_eq!(allocator.allocate(), 1);
+        assert_eq!(allocator.allocate(), 5);
+    }
+
+    #[test]
+    fn allocators_round_trip() {
+        let mut allocator = IdAllocator::<u64>::new();
+        for _ in 0..3 {
+            allocator.allocate();
+        }
+        allocator.free(1);
+        let loaded: IdAllocator<u64> = serde_json::from_str(&serde_json::to_string(&allocator).unwrap()).unwrap();
+        assert!(loaded.is_allocated(0));
+        assert!(!loaded.is_allocated(1));
+        assert_eq!(loaded.peek(), 3);
+    }
+}
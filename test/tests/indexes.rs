@@ -0,0 +1,65 @@
+#[macro_use] extern crate entity_store_test;
+
+tests! {
+    fn sorted<I: Iterator<Item = EntityId>>(ids: I) -> Vec<EntityId> {
+        let mut ids = ids.collect::<Vec<_>>();
+        ids.sort();
+        ids
+    }
+
+    fn tiles_in_range<R: ::std::ops::RangeBounds<u8>>(world: &EntityWorld, range: R) -> Vec<(u8, EntityId)> {
+        let tiles = world.entity_store().entities_with_tile_in_range(range)
+            .map(|(id, &tile)| (tile, id))
+            .collect::<Vec<_>>();
+        let mut sorted = tiles.clone();
+        sorted.sort();
+        assert_eq!(tiles, sorted, "ranges are iterated in order of value");
+        tiles
+    }
+
+    #[test]
+    fn hash_index_follows_updates_and_removals() {
+        let mut world = world();
+        let ids = (0..4).map(|_| world.spawn()).collect::<Vec<_>>();
+        for (i, &id) in ids.iter().enumerate() {
+            world.commit(insert::faction(id, i as u32 % 2)).unwrap();
+        }
+        assert_eq!(sorted(world.entity_store().entities_with_faction(&0)), vec![ids[0], ids[2]]);
+        assert_eq!(sorted(world.entity_store().entities_with_faction(&1)), vec![ids[1], ids[3]]);
+
+        world.commit(insert::faction(ids[0], 1)).unwrap();
+        world.commit(insert::faction(ids[3], 1)).unwrap();
+        assert_eq!(sorted(world.entity_store().entities_with_faction(&0)), vec![ids[2]]);
+        assert_eq!(sorted(world.entity_store().entities_with_faction(&1)), vec![ids[0], ids[1], ids[3]]);
+
+        world.commit(remove::faction(ids[2])).unwrap();
+        world.despawn(ids[1]);
+        assert_eq!(world.entity_store().entities_with_faction(&0).count(), 0);
+        assert_eq!(sorted(world.entity_store().entities_with_faction(&1)), vec![ids[0], ids[3]]);
+        assert_eq!(world.entity_store().entities_with_faction(&2).count(), 0);
+    }
+
+    #[test]
+    fn btree_index_follows_updates_and_removals() {
+        let mut world = world();
+        let ids = (0..4).map(|_| world.spawn()).collect::<Vec<_>>();
+        for (i, &id) in ids.iter().enumerate() {
+            world.commit(insert::tile(id, i as u8 * 10)).unwrap();
+        }
+        assert_eq!(sorted(world.entity_store().entities_with_tile(&20)), vec![ids[2]]);
+        assert_eq!(tiles_in_range(&world, 10..30), vec![(10, ids[1]), (20, ids[2])]);
+        assert_eq!(tiles_in_range(&world, ..).len(), 4);
+
+        world.commit(insert::tile(ids[0], 25)).unwrap();
+        world.commit(insert::tile(ids[2], 25)).unwrap();
+        assert_eq!(world.entity_store().entities_with_tile(&0).count(), 0);
+        assert_eq!(world.entity_store().entities_with_tile(&20).count(), 0);
+        assert_eq!(sorted(world.entity_store().entities_with_tile(&25)), vec![ids[0], ids[2]]);
+        assert_eq!(tiles_in_range(&world, 20..=25), vec![(25, ids[0]), (25, ids[2])]);
+
+        world.commit(remove::tile(ids[0])).unwrap();
+        world.despawn(ids[1]);
+        assert_eq!(sorted(world.entity_store().entities_with_tile(&25)), vec![ids[2]]);
+        assert_eq!(tiles_in_range(&world, ..), vec![(25, ids[2]), (30, ids[3])]);
+    }
+}
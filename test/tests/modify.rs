@@ -0,0 +1,49 @@
+#[macro_use] extern crate entity_store_test;
+
+tests! {
+    #[test]
+    fn modified_values_are_buffered_until_committed() {
+        let mut world = world();
+        let id = EntityBuilder::new().coord(Coord::new(1, 1)).solid().health(4).spawn(&mut world).unwrap();
+        let mut changes = Vec::new();
+        {
+            let mut coord = world.entity_store().modify_coord(id, &mut changes).unwrap();
+            assert_eq!(coord.id(), id);
+            *coord = Coord::new(2, 3);
+        }
+        {
+            let mut health = world.entity_store().modify_health(id, &mut changes).unwrap();
+            *health += 1;
+        }
+        assert_eq!(changes.len(), 2);
+        assert_eq!(world.entity_store().get_coord(id), Some(&Coord::new(1, 1)));
+
+        world.commit_batch(changes).unwrap();
+        assert_eq!(world.entity_store().get_coord(id), Some(&Coord::new(2, 3)));
+        assert_eq!(world.entity_store().get_health(id), Some(&5));
+        assert_eq!(world.spatial_hash().get(Coord::new(1, 1)).unwrap().solid_count, 0);
+        assert_eq!(world.spatial_hash().get(Coord::new(2, 3)).unwrap().solid_count, 1);
+    }
+
+    #[test]
+    fn reading_through_a_guard_records_nothing() {
+        let mut world = world();
+        let id = EntityBuilder::new().tile(2).spawn(&mut world).unwrap();
+        let mut changes = Vec::new();
+        {
+            let tile = world.entity_store().modify_tile(id, &mut changes).unwrap();
+            assert_eq!(*tile, 2);
+        }
+        assert!(changes.is_empty());
+    }
+
+    #[test]
+    fn absent_components_have_no_guard() {
+        let mut world = world();
+        let id = world.spawn();
+        let mut changes = Vec::new();
+        assert!(world.entity_store().modify_tile(id, &mut changes).is_none());
+        assert!(world.entity_store().modify_health(id, &mut changes).is_none());
+        assert!(changes.is_empty());
+    }
+}
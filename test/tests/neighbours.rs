@@ -0,0 +1,83 @@
+#[macro_use] extern crate entity_store_test;
+extern crate entity_store_helper;
+
+tests! {
+    use entity_store_helper::direction::Direction;
+
+    fn solid_total(world: &EntityWorld) -> u32 {
+        world.spatial_hash().enumerate().map(|(_, cell)| cell.solid_neighbours.total() as u32).sum()
+    }
+
+    #[test]
+    fn counts_cover_the_configured_neighbourhood() {
+        let mut world = world();
+        let centre = Coord::new(3, 3);
+        EntityBuilder::new().coord(centre).solid().spawn(&mut world).unwrap();
+        EntityBuilder::new().coord(centre).door().spawn(&mut world).unwrap();
+
+        let hash = world.spatial_hash();
+        let cell = |x, y| hash.get(Coord::new(x, y)).unwrap();
+        // each neighbour sees the entity in the opposite direction
+        assert_eq!(cell(3, 2).solid_neighbours.get(Direction::South), 1);
+        assert_eq!(cell(4, 4).solid_neighbours.get(Direction::NorthWest), 1);
+        assert_eq!(cell(4, 4).solid_neighbours.total(), 1);
+        assert_eq!(cell(3, 3).solid_neighbours.total(), 0);
+        assert_eq!(solid_total(&world), 8);
+
+        // doors are counted in cardinal directions only
+        assert_eq!(cell(4, 3).door_neighbours.get(Direction::West), 1);
+        assert_eq!(cell(3, 4).door_neighbours.bitmap_raw(), 1 << Direction::North as u8);
+        assert_eq!(cell(4, 4).door_neighbours.total(), 0);
+        assert_eq!(cell(2, 2).door_neighbours.total(), 0);
+    }
+
+    #[test]
+    fn counts_are_clipped_at_grid_edges() {
+        let mut world = world();
+        let corner = EntityBuilder::new().coord(Coord::new(0, 0)).solid().spawn(&mut world).unwrap();
+        assert_eq!(solid_total(&world), 3);
+        assert_eq!(world.spatial_hash().get(Coord::new(1, 1)).unwrap().solid_neighbours.get(Direction::NorthWest), 1);
+
+        let edge = EntityBuilder::new().coord(Coord::new(7, 4)).solid().spawn(&mut world).unwrap();
+        assert_eq!(solid_total(&world), 8);
+
+        world.commit(insert::coord(corner, Coord::new(1, 0))).unwrap();
+        assert_eq!(solid_total(&world), 10);
+        assert_eq!(world.spatial_hash().get(Coord::new(1, 1)).unwrap().solid_neighbours.get(Direction::North), 1);
+        assert_eq!(world.spatial_hash().get(Coord::new(1, 1)).unwrap().solid_neighbours.get(Direction::NorthWest), 0);
+
+        world.despawn(edge);
+        world.commit(remove::solid(corner)).unwrap();
+        assert_eq!(solid_total(&world), 0);
+    }
+
+    #[test]
+    fn sums_follow_values() {
+        let mut world = world();
+        let centre = Coord::new(3, 3);
+        let a = EntityBuilder::new().coord(centre).health(5).spawn(&mut world).unwrap();
+        let b = EntityBuilder::new().coord(centre).health(2).spawn(&mut world).unwrap();
+        let sum = |world: &EntityWorld, x, y, direction| {
+            world.spatial_hash().get(Coord::new(x, y)).unwrap().health_neighbours.get(direction)
+        };
+
+        // health is summed in ordinal directions only
+        assert_eq!(sum(&world, 4, 4, Direction::NorthWest), 7);
+        assert_eq!(sum(&world, 2, 2, Direction::SouthEast), 7);
+        assert_eq!(world.spatial_hash().get(Coord::new(4, 3)).unwrap().health_neighbours.total(), 0);
+
+        world.commit(insert::health(a, 1)).unwrap();
+        assert_eq!(sum(&world, 4, 4, Direction::NorthWest), 3);
+        world.commit(remove::health(b)).unwrap();
+        assert_eq!(sum(&world, 4, 4, Direction::NorthWest), 1);
+        world.commit(insert::coord(a, Coord::new(5, 5))).unwrap();
+        assert_eq!(sum(&world, 4, 4, Direction::NorthWest), 0);
+        assert_eq!(sum(&world, 4, 4, Direction::SouthEast), 1);
+
+        // the items hash sums in all directions
+        let items = world.spatial_hash_items();
+        assert_eq!(items.get(Coord::new(5, 4)).unwrap().health_around.get(Direction::South), 1);
+        assert_eq!(items.get(Coord::new(4, 4)).unwrap().health_around.total(), 1);
+        assert_eq!(items.enumerate().map(|(_, cell)| cell.health_around.total()).sum::<i32>(), 8);
+    }
+}
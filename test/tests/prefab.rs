@@ -0,0 +1,44 @@
+#[macro_use] extern crate entity_store_test;
+
+tests! {
+    fn debug(changes: Vec<EntityChange>) -> Vec<String> {
+        changes.iter().map(|change| format!("{:?}", change)).collect()
+    }
+
+    #[test]
+    fn defaults_come_from_the_spec() {
+        let mut world = world();
+        let id = world.spawn();
+        let prefab = GoblinPrefab::new();
+        assert_eq!(prefab.coord, Coord::new(1, 2));
+        assert_eq!(prefab.health, 10);
+        assert_eq!(prefab.opacity, 0.5);
+        assert_eq!(debug(spawn::goblin(id)), debug(GoblinPrefab::default().changes(id)));
+
+        world.commit_batch(spawn::goblin(id)).unwrap();
+        let store = world.entity_store();
+        assert_eq!(store.get_coord(id), Some(&Coord::new(1, 2)));
+        assert_eq!(store.get_health(id), Some(&10));
+        assert_eq!(store.get_opacity(id), Some(&0.5));
+        assert!(store.contains_solid(id));
+        assert_eq!(world.entity_component_table().component_types(id).count(), 4);
+        assert_eq!(world.spatial_hash().get(Coord::new(1, 2)).unwrap().solid_count, 1);
+    }
+
+    #[test]
+    fn overridden_values_are_inserted() {
+        let mut world = world();
+        let prefab = GoblinPrefab::new().coord(Coord::new(3, 4)).health(7);
+        let a = world.spawn();
+        let b = world.spawn();
+        world.commit_batch(prefab.changes(a)).unwrap();
+        world.commit_batch(prefab.changes(b)).unwrap();
+        for &id in [a, b].iter() {
+            assert_eq!(world.entity_store().get_coord(id), Some(&Coord::new(3, 4)));
+            assert_eq!(world.entity_store().get_health(id), Some(&7));
+            assert!(world.entity_store().contains_solid(id));
+        }
+        assert_eq!(world.spatial_hash().get(Coord::new(3, 4)).unwrap().solid_count, 2);
+        assert_eq!(world.spatial_hash().get(Coord::new(1, 2)).unwrap().solid_count, 0);
+    }
+}
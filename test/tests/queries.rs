@@ -0,0 +1,68 @@
+#[macro_use] extern crate entity_store_test;
+
+tests! {
+    #[test]
+    fn required_components_must_all_be_present() {
+        let mut world = world();
+        let a = EntityBuilder::new().solid().tile(1).spawn(&mut world).unwrap();
+        let b = EntityBuilder::new().solid().spawn(&mut world).unwrap();
+        let c = EntityBuilder::new().tile(2).spawn(&mut world).unwrap();
+        let d = EntityBuilder::new().solid().tile(3).coord(Coord::new(1, 1)).spawn(&mut world).unwrap();
+
+        let mut found = world.entity_store().query_solid_tiles().map(|(id, &tile)| (id, tile)).collect::<Vec<_>>();
+        found.sort();
+        assert_eq!(found, vec![(a, 1), (d, 3)]);
+        assert!(!found.iter().any(|&(id, _)| id == b || id == c));
+
+        let players = (0..2).map(|_| EntityBuilder::new().player().spawn(&mut world).unwrap()).collect::<Vec<_>>();
+        let mut found = world.entity_store().query_players().collect::<Vec<_>>();
+        found.sort();
+        assert_eq!(found, players);
+    }
+
+    #[test]
+    fn optional_components_are_yielded_when_present() {
+        let mut world = world();
+        let a = EntityBuilder::new().coord(Coord::new(1, 1)).velocity((1, 0)).spawn(&mut world).unwrap();
+        let b = EntityBuilder::new().coord(Coord::new(2, 2)).velocity((0, 1)).tile(4).solid().spawn(&mut world).unwrap();
+
+        let mut found = world.entity_store().query_moving()
+            .map(|(id, &coord, &velocity, tile, solid)| (id, coord, velocity, tile.cloned(), solid))
+            .collect::<Vec<_>>();
+        found.sort_by_key(|&(id, ..)| id);
+        assert_eq!(found, vec![
+            (a, Coord::new(1, 1), (1, 0), None, false),
+            (b, Coord::new(2, 2), (0, 1), Some(4), true),
+        ]);
+
+        world.commit(insert::player(a)).unwrap();
+        let living = EntityBuilder::new().coord(Coord::new(3, 3)).health(5).player().spawn(&mut world).unwrap();
+        let found = world.entity_store().query_living().map(|(id, &health, _, player)| (id, health, player)).collect::<Vec<_>>();
+        assert_eq!(found, vec![(living, 5, true)]);
+    }
+
+    #[test]
+    fn excluded_components_skip_entities() {
+        let mut world = world();
+        let a = EntityBuilder::new().coord(Coord::new(1, 1)).velocity((1, 0)).spawn(&mut world).unwrap();
+        let b = EntityBuilder::new().coord(Coord::new(2, 2)).velocity((0, 1)).door().spawn(&mut world).unwrap();
+        let ids = |world: &EntityWorld| world.entity_store().query_moving().map(|(id, ..)| id).collect::<Vec<_>>();
+        assert_eq!(ids(&world), vec![a]);
+
+        world.commit(remove::door(b)).unwrap();
+        world.commit(insert::door(a)).unwrap();
+        assert_eq!(ids(&world), vec![b]);
+    }
+
+    #[test]
+    fn queries_follow_removals() {
+        let mut world = world();
+        let ids = (0..4).map(|i| EntityBuilder::new().solid().tile(i).spawn(&mut world).unwrap()).collect::<Vec<_>>();
+        world.commit(remove::tile(ids[1])).unwrap();
+        world.despawn(ids[2]);
+
+        let mut found = world.entity_store().query_solid_tiles().map(|(id, _)| id).collect::<Vec<_>>();
+        found.sort();
+        assert_eq!(found, vec![ids[0], ids[3]]);
+    }
+}
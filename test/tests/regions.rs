@@ -0,0 +1,64 @@
+#[macro_use] extern crate entity_store_test;
+
+tests! {
+    fn sorted<I: Iterator<Item = Coord>>(coords: I) -> Vec<(i32, i32)> {
+        let mut coords = coords.map(|coord| (coord.x, coord.y)).collect::<Vec<_>>();
+        coords.sort();
+        coords
+    }
+
+    #[test]
+    fn rects_are_clipped_to_the_grid() {
+        let world = world();
+        let hash = world.spatial_hash();
+        assert_eq!(sorted(hash.rect(Coord::new(-1, -1), Size::new(2, 3)).map(|(coord, _)| coord)),
+                   vec![(0, 0), (0, 1)]);
+        assert_eq!(sorted(hash.rect(Coord::new(6, 7), Size::new(4, 4)).map(|(coord, _)| coord)),
+                   vec![(6, 7), (7, 7)]);
+        assert_eq!(hash.rect(Coord::new(-4, -4), Size::new(16, 16)).count(), 64);
+        assert_eq!(hash.rect(Coord::new(8, 0), Size::new(2, 2)).count(), 0);
+        assert_eq!(hash.rect(Coord::new(-3, 2), Size::new(3, 2)).count(), 0);
+        assert_eq!(world.spatial_hash_regions().rect(Coord::new(2, 2), Size::new(5, 5)).count(), 4);
+    }
+
+    #[test]
+    fn radii_are_clipped_to_the_grid() {
+        let world = world();
+        let hash = world.spatial_hash();
+        assert_eq!(sorted(hash.chebyshev_radius(Coord::new(0, 0), 1).map(|(coord, _)| coord)),
+                   vec![(0, 0), (0, 1), (1, 0), (1, 1)]);
+        assert_eq!(sorted(hash.euclidean_radius(Coord::new(7, 7), 1).map(|(coord, _)| coord)),
+                   vec![(6, 7), (7, 6), (7, 7)]);
+        assert_eq!(hash.chebyshev_radius(Coord::new(3, 3), 1).count(), 9);
+        assert_eq!(hash.euclidean_radius(Coord::new(3, 3), 2).count(), 13);
+        assert_eq!(hash.chebyshev_radius(Coord::new(-1, 4), 1).count(), 3);
+        assert_eq!(hash.euclidean_radius(Coord::new(-5, -5), 2).count(), 0);
+        assert_eq!(world.spatial_hash_items().chebyshev_radius(Coord::new(7, 0), 20).count(), 64);
+        assert_eq!(world.spatial_hash_regions().euclidean_radius(Coord::new(0, 3), 1).count(), 3);
+    }
+
+    #[test]
+    fn entities_are_found_in_regions() {
+        let mut world = world();
+        let corner = EntityBuilder::new().coord(Coord::new(0, 0)).tile(1).spawn(&mut world).unwrap();
+        let edge = EntityBuilder::new().coord(Coord::new(0, 2)).tile(2).spawn(&mut world).unwrap();
+        let centre = EntityBuilder::new().coord(Coord::new(4, 4)).tile(3).spawn(&mut world).unwrap();
+
+        let in_region = |region| {
+            let mut ids = world.spatial_hash().entities_in_region(region).collect::<Vec<_>>();
+            ids.sort();
+            ids
+        };
+        assert_eq!(in_region(Region::Rect(Coord::new(-2, -2), Size::new(3, 5))), vec![corner, edge]);
+        assert_eq!(in_region(Region::Chebyshev(Coord::new(-1, -1), 1)), vec![corner]);
+        assert_eq!(in_region(Region::Euclidean(Coord::new(0, 1), 1)), vec![corner, edge]);
+        assert_eq!(in_region(Region::Euclidean(Coord::new(2, 2), 2)), vec![edge]);
+        assert_eq!(in_region(Region::Chebyshev(Coord::new(2, 2), 2)), vec![corner, edge, centre]);
+        assert!(in_region(Region::Rect(Coord::new(8, 8), Size::new(4, 4))).is_empty());
+
+        let tiles = world.spatial_hash_items().rect(Coord::new(-1, -1), Size::new(2, 4))
+            .map(|(_, cell)| cell.tiles.len())
+            .sum::<usize>();
+        assert_eq!(tiles, 2);
+    }
+}
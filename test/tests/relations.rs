@@ -0,0 +1,110 @@
+#[macro_use] extern crate entity_store_test;
+
+tests! {
+    fn is_allocated(world: &EntityWorld, id: EntityId) -> bool {
+        if let Err(CommitError::UnallocatedId(_)) = world.check_commit(&insert::solid(id)) {
+            return false;
+        }
+        true
+    }
+
+    #[test]
+    fn children_are_tracked() {
+        let mut world = world();
+        let parent = world.spawn();
+        let a = world.spawn();
+        let b = world.spawn();
+        world.commit(insert::contained_in(a, parent)).unwrap();
+        world.commit(insert::contained_in(b, parent)).unwrap();
+        assert_eq!(world.entity_store().children_contained_in(parent), &[a, b]);
+
+        world.commit(insert::contained_in(a, b)).unwrap();
+        assert_eq!(world.entity_store().children_contained_in(parent), &[b]);
+        assert_eq!(world.entity_store().children_contained_in(b), &[a]);
+
+        world.commit(remove::contained_in(b)).unwrap();
+        assert!(world.entity_store().children_contained_in(parent).is_empty());
+    }
+
+    #[test]
+    fn despawning_cascades_and_frees_ids() {
+        let mut world = world();
+        let parent = world.spawn();
+        let child = world.spawn();
+        let grandchild = world.spawn();
+        let item = world.spawn();
+        world.commit(insert::coord(parent, Coord::new(1, 1))).unwrap();
+        world.commit(insert::contained_in(child, parent)).unwrap();
+        world.commit(insert::coord(child, Coord::new(1, 1))).unwrap();
+        world.commit(insert::solid(child)).unwrap();
+        world.commit(insert::contained_in(grandchild, child)).unwrap();
+        world.commit(insert::tile(grandchild, 2)).unwrap();
+        world.commit(insert::owner(item, child)).unwrap();
+        world.commit(insert::tile(item, 5)).unwrap();
+
+        world.despawn(parent);
+
+        let store = world.entity_store();
+        assert_eq!(store.get_coord(child), None);
+        assert!(!store.contains_solid(child));
+        assert_eq!(store.get_tile(grandchild), None);
+        assert_eq!(world.spatial_hash().get(Coord::new(1, 1)).unwrap().solid_count, 0);
+        assert!(!is_allocated(&world, parent));
+        assert!(!is_allocated(&world, child));
+        assert!(!is_allocated(&world, grandchild));
+
+        // detached rather than removed
+        assert!(is_allocated(&world, item));
+        assert_eq!(store.get_owner(item), None);
+        assert_eq!(store.get_tile(item), Some(&5));
+        assert!(store.children_owner(child).is_empty());
+
+        // each freed id is handed out again exactly once
+        let mut respawned = (0..4).map(|_| world.spawn()).collect::<Vec<_>>();
+        respawned.sort();
+        respawned.dedup();
+        assert_eq!(respawned.len(), 4);
+        assert!(!respawned.contains(&item));
+    }
+
+    #[test]
+    fn only_cascading_removal_includes_related_entities() {
+        let mut world = world();
+        let parent = world.spawn();
+        let child = world.spawn();
+        let item = world.spawn();
+        world.commit(insert::solid(parent)).unwrap();
+        world.commit(insert::contained_in(child, parent)).unwrap();
+        world.commit(insert::owner(item, parent)).unwrap();
+        let table = world.entity_component_table();
+
+        let changes = table.remove_entity(parent);
+        assert!(changes.cascaded_ids().is_empty());
+        assert_eq!(changes.count(), 1);
+
+        let changes = table.remove_entity_cascading(parent, world.entity_store());
+        assert_eq!(changes.cascaded_ids(), &[child]);
+        let changes = changes.collect::<Vec<_>>();
+        assert_eq!(changes.len(), 3);
+        assert!(changes.iter().any(|change| change.id() == child && change.typ() == ComponentType::ContainedIn));
+        assert!(changes.iter().any(|change| change.id() == item && change.typ() == ComponentType::Owner));
+    }
+
+    #[test]
+    fn despawning_a_cycle_terminates() {
+        let mut world = world();
+        let a = world.spawn();
+        let b = world.spawn();
+        world.commit(insert::contained_in(a, b)).unwrap();
+        world.commit(insert::contained_in(b, a)).unwrap();
+
+        world.despawn(a);
+        assert!(!world.entity_store().contains_contained_in(b));
+        assert!(!is_allocated(&world, a));
+        assert!(!is_allocated(&world, b));
+        let mut respawned = (0..3).map(|_| world.spawn()).collect::<Vec<_>>();
+        respawned.sort();
+        respawned.dedup();
+        assert_eq!(respawned.len(), 3);
+    }
+}
@@ -0,0 +1,83 @@
+#[macro_use] extern crate entity_store_test;
+extern crate serde_json;
+
+tests! {
+    use serde_json;
+
+    #[test]
+    fn resources_are_set_and_cleared_by_changes() {
+        let mut world = world();
+        assert_eq!(world.entity_store().turn(), None);
+        world.commit(set::turn(3)).unwrap();
+        world.commit(set::spawn_point(Coord::new(1, 2))).unwrap();
+        world.commit(set::seed(7)).unwrap();
+        world.commit(clear::seed()).unwrap();
+
+        let store = world.entity_store();
+        assert_eq!(store.turn(), Some(&3));
+        assert_eq!(store.spawn_point(), Some(&Coord::new(1, 2)));
+        assert_eq!(store.seed(), None);
+        match store.get_resource(ResourceType::Turn) {
+            Some(ResourceRef::Turn(&turn)) => assert_eq!(turn, 3),
+            _ => panic!("turn missing"),
+        }
+        assert!(store.get_resource(ResourceType::RngSeed).is_none());
+    }
+
+    #[test]
+    fn setters_return_previous_values() {
+        let mut store = world().entity_store().clone();
+        assert_eq!(store.set_turn(1), None);
+        assert_eq!(store.set_turn(2), Some(1));
+        assert_eq!(store.clear_turn(), Some(2));
+        assert_eq!(store.clear_turn(), None);
+
+        match store.set_resource(ResourceValue::RngSeed(4)) {
+            None => (),
+            Some(_) => panic!("unexpected previous seed"),
+        }
+        match store.clear_resource(ResourceType::RngSeed) {
+            Some(ResourceValue::RngSeed(seed)) => assert_eq!(seed, 4),
+            other => panic!("unexpected previous seed: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn resource_changes_have_a_resource_type() {
+        let id = world().spawn();
+        assert_eq!(set::turn(1).resource_typ(), Some(ResourceType::Turn));
+        assert_eq!(clear::seed().resource_typ(), Some(ResourceType::RngSeed));
+        assert_eq!(insert::tile(id, 1).resource_typ(), None);
+        assert_eq!(insert::tile(id, 1).id(), id);
+        assert_eq!(remove::tile(id).typ(), ComponentType::Tile);
+    }
+
+    #[test]
+    #[should_panic]
+    fn resource_changes_have_no_entity() {
+        set::turn(1).id();
+    }
+
+    #[test]
+    fn resources_are_cloned_and_serialized() {
+        let mut world = world();
+        let id = world.spawn();
+        world.commit(insert::tile(id, 1)).unwrap();
+        world.commit(set::turn(5)).unwrap();
+        world.commit(set::spawn_point(Coord::new(3, 3))).unwrap();
+
+        let mut changes = Vec::new();
+        world.entity_store().clone_changes(&mut changes);
+        let resource_types = changes.iter().filter_map(|change| change.resource_typ()).collect::<Vec<_>>();
+        assert_eq!(resource_types.len(), 2);
+        assert!(resource_types.contains(&ResourceType::Turn));
+        assert!(resource_types.contains(&ResourceType::SpawnPoint));
+
+        // json maps need string keys, so only the resources are serialized
+        let json = serde_json::to_string(world.entity_store().resources()).unwrap();
+        let loaded: Resources = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.turn, Some(5));
+        assert_eq!(loaded.spawn_point, Some(Coord::new(3, 3)));
+        assert_eq!(loaded.seed, None);
+    }
+}
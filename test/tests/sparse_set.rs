@@ -0,0 +1,110 @@
+#[macro_use] extern crate entity_store_test;
+
+tests! {
+    fn ids(count: usize) -> Vec<EntityId> {
+        let mut world = world();
+        (0..count).map(|_| world.spawn()).collect()
+    }
+
+    #[test]
+    fn maps_keep_values_dense_across_removals() {
+        let ids = ids(4);
+        let mut map = EntitySparseSetMap::new();
+        for (i, &id) in ids.iter().enumerate().rev() {
+            assert_eq!(map.insert(id, i), None);
+        }
+        assert_eq!(map.insert(ids[2], 20), Some(2));
+        assert_eq!(map.len(), 4);
+
+        // removing swaps the last value into the gap
+        assert_eq!(map.remove(&ids[3]), Some(3));
+        assert_eq!(map.remove(&ids[3]), None);
+        assert_eq!(map.iter().map(|(id, &value)| (id, value)).collect::<Vec<_>>(),
+                   vec![(ids[0], 0), (ids[2], 20), (ids[1], 1)]);
+        for &(id, value) in [(ids[0], 0), (ids[1], 1), (ids[2], 20)].iter() {
+            assert_eq!(map.get(&id), Some(&value));
+            assert!(map.contains_key(&id));
+        }
+        assert!(!map.contains_key(&ids[3]));
+
+        *map.get_mut(&ids[1]).unwrap() += 10;
+        *map.entry(&ids[0]).or_insert(5) += 1;
+        *map.entry(&ids[3]).or_insert_with(|| 7) += 1;
+        assert_eq!(map.get(&ids[0]), Some(&1));
+        assert_eq!(map.get(&ids[1]), Some(&11));
+        assert_eq!(map.get(&ids[3]), Some(&8));
+
+        map.clear();
+        assert!(map.is_empty());
+        assert_eq!(map.get(&ids[0]), None);
+    }
+
+    #[test]
+    fn sets_keep_ids_dense_across_removals() {
+        let ids = ids(4);
+        let mut set = EntitySparseSet::new();
+        for &id in ids.iter() {
+            assert!(!set.insert(id));
+        }
+        assert!(set.insert(ids[1]));
+        assert!(set.remove(&ids[0]));
+        assert!(!set.remove(&ids[0]));
+        assert_eq!(set.iter().collect::<Vec<_>>(), vec![ids[3], ids[1], ids[2]]);
+        assert!(!set.contains(&ids[0]));
+        assert!(set.contains(&ids[3]));
+        assert_eq!(set.len(), 3);
+    }
+
+    #[test]
+    fn sparse_set_components_behave_like_others() {
+        let mut world = world();
+        let ids = (0..3).map(|_| world.spawn()).collect::<Vec<_>>();
+        for (i, &id) in ids.iter().enumerate() {
+            world.commit(insert::health(id, i as i32)).unwrap();
+            world.commit(insert::player(id)).unwrap();
+        }
+        world.commit(remove::health(ids[0])).unwrap();
+        world.commit(remove::player(ids[1])).unwrap();
+        world.despawn(ids[2]);
+
+        let store = world.entity_store();
+        assert_eq!(store.get_health(ids[0]), None);
+        assert_eq!(store.get_health(ids[1]), Some(&1));
+        assert!(store.contains_player(ids[0]));
+        assert!(!store.contains_player(ids[1]));
+        assert!(!store.contains_player(ids[2]));
+        assert_eq!(store.get_health(ids[2]), None);
+
+        let id = world.spawn();
+        world.commit(insert::health(id, 9)).unwrap();
+        assert_eq!(world.entity_store().get_health(id), Some(&9));
+        assert_eq!(world.entity_store().get_health(ids[1]), Some(&1));
+    }
+}
+
+mod generational {
+    tests!(component_generational, archetype_generational => {
+        #[test]
+        fn stale_ids_are_not_found() {
+            let mut world = world();
+            let stale = world.spawn();
+            world.despawn(stale);
+            let id = world.spawn();
+
+            let mut map = EntitySparseSetMap::new();
+            map.insert(stale, 1);
+            assert_eq!(map.get(&id), None);
+            map.insert(id, 2);
+            assert_eq!(map.get(&stale), None);
+            assert_eq!(map.insert(stale, 3), None);
+            assert_eq!(map.get(&id), Some(&2));
+            assert_eq!(map.len(), 1);
+
+            let mut set = EntitySparseSet::new();
+            set.insert(id);
+            assert!(!set.insert(stale));
+            assert!(!set.remove(&stale));
+            assert!(set.contains(&id));
+        }
+    });
+}
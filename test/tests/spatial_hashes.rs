@@ -0,0 +1,79 @@
+#[macro_use] extern crate entity_store_test;
+
+tests! {
+    #[test]
+    fn each_hash_has_its_own_size() {
+        let world = world();
+        assert_eq!(world.spatial_hash().size(), Size::new(8, 8));
+        assert_eq!(world.spatial_hash_items().size(), Size::new(8, 8));
+        assert_eq!(world.spatial_hash_regions().size(), Size::new(4, 4));
+        assert!(world.spatial_hash_regions().get(Coord::new(4, 0)).is_none());
+    }
+
+    #[test]
+    fn each_hash_follows_its_own_position_component() {
+        let mut world = world();
+        let id = EntityBuilder::new().coord(Coord::new(1, 1)).region(Coord::new(3, 3)).solid().spawn(&mut world).unwrap();
+        assert_eq!(world.spatial_hash().get(Coord::new(1, 1)).unwrap().solid_count, 1);
+        assert_eq!(world.spatial_hash_regions().get(Coord::new(3, 3)).unwrap().solid_count, 1);
+        assert_eq!(world.spatial_hash_regions().get(Coord::new(1, 1)).unwrap().solid_count, 0);
+
+        world.commit(insert::coord(id, Coord::new(5, 5))).unwrap();
+        assert_eq!(world.spatial_hash().get(Coord::new(5, 5)).unwrap().solid_count, 1);
+        assert_eq!(world.spatial_hash_regions().get(Coord::new(3, 3)).unwrap().solid_count, 1);
+
+        world.commit(insert::region(id, Coord::new(0, 2))).unwrap();
+        assert_eq!(world.spatial_hash_regions().get(Coord::new(3, 3)).unwrap().solid_count, 0);
+        assert_eq!(world.spatial_hash_regions().get(Coord::new(0, 2)).unwrap().solid_count, 1);
+
+        // an entity without the position component isn't in the hash
+        world.commit(remove::region(id)).unwrap();
+        assert_eq!(world.spatial_hash_regions().iter().map(|cell| cell.solid_count).sum::<usize>(), 0);
+        assert_eq!(world.spatial_hash().get(Coord::new(5, 5)).unwrap().solid_count, 1);
+    }
+
+    #[test]
+    fn each_hash_has_its_own_aggregates() {
+        let mut world = world();
+        let coord = Coord::new(2, 2);
+        let a = EntityBuilder::new().coord(coord).tile(1).health(3).spawn(&mut world).unwrap();
+        let b = EntityBuilder::new().coord(coord).health(4).spawn(&mut world).unwrap();
+
+        let cell = world.spatial_hash_items().get(coord).unwrap();
+        assert_eq!(cell.health_total, 7);
+        assert_eq!(cell.tiles.iter().cloned().collect::<Vec<_>>(), vec![a]);
+        assert_eq!(world.spatial_hash().get(coord).unwrap().highest_health.get(), Some(&4));
+        assert_eq!(world.spatial_hash_items().get(Coord::new(3, 2)).unwrap().health_around.total(), 7);
+
+        world.commit(remove::health(b)).unwrap();
+        world.commit(insert::tile(b, 2)).unwrap();
+        let cell = world.spatial_hash_items().get(coord).unwrap();
+        assert_eq!(cell.health_total, 3);
+        assert_eq!(cell.tiles.len(), 2);
+        assert_eq!(world.spatial_hash_items().get(Coord::new(3, 2)).unwrap().health_around.total(), 3);
+    }
+
+    #[test]
+    fn each_hash_is_affected_by_its_own_components() {
+        assert!(ItemsSpatialHashTable::affected_by(ComponentType::Coord));
+        assert!(ItemsSpatialHashTable::affected_by(ComponentType::Tile));
+        assert!(!ItemsSpatialHashTable::affected_by(ComponentType::Region));
+        assert!(!ItemsSpatialHashTable::affected_by(ComponentType::Solid));
+        assert!(RegionsSpatialHashTable::affected_by(ComponentType::Region));
+        assert!(RegionsSpatialHashTable::affected_by(ComponentType::Solid));
+        assert!(!RegionsSpatialHashTable::affected_by(ComponentType::Coord));
+        assert!(SpatialHashTable::affected_by(ComponentType::Solid));
+        assert!(!SpatialHashTable::affected_by(ComponentType::Region));
+    }
+
+    #[test]
+    fn updates_are_timestamped_per_hash() {
+        let mut world = world();
+        world.set_time(3);
+        let id = EntityBuilder::new().coord(Coord::new(1, 1)).region(Coord::new(1, 1)).spawn(&mut world).unwrap();
+        world.set_time(5);
+        world.commit(insert::tile(id, 1)).unwrap();
+        assert_eq!(world.spatial_hash_items().get(Coord::new(1, 1)).unwrap().last_updated, 5);
+        assert_eq!(world.spatial_hash_regions().get(Coord::new(1, 1)).unwrap().last_updated, 3);
+    }
+}
@@ -0,0 +1,93 @@
+#[macro_use] extern crate entity_store_test;
+
+tests! {
+    use std::borrow::Borrow;
+
+    fn sorted<T: Borrow<EntityId>, I: IntoIterator<Item = T>>(ids: I) -> Vec<EntityId> {
+        let mut ids = ids.into_iter().map(|id| *id.borrow()).collect::<Vec<_>>();
+        ids.sort();
+        ids
+    }
+
+    // Tracking is driven through a store so that changes can be cleared.
+    fn store(count: usize) -> (EntityStore, Vec<EntityId>) {
+        let mut world = world();
+        let ids = (0..count).map(|_| world.spawn()).collect();
+        (world.entity_store().clone(), ids)
+    }
+
+    #[test]
+    fn inserts_are_added_then_changed() {
+        let (mut store, ids) = store(2);
+        let (a, b) = (ids[0], ids[1]);
+        store.commit(insert::coord(a, Coord::new(0, 0))).unwrap();
+        store.commit(insert::coord(a, Coord::new(1, 0))).unwrap();
+        store.commit(insert::door(b)).unwrap();
+        store.commit(insert::health(b, 1)).unwrap();
+        assert_eq!(sorted(store.added_coord().iter()), vec![a]);
+        assert!(store.changed_coord().is_empty());
+        assert_eq!(sorted(store.added_door().iter()), vec![b]);
+        assert_eq!(sorted(store.added_health().iter()), vec![b]);
+
+        store.clear_changes();
+        store.commit(insert::coord(a, Coord::new(2, 0))).unwrap();
+        store.commit(insert::health(b, 2)).unwrap();
+        assert!(store.added_coord().is_empty());
+        assert_eq!(sorted(store.changed_coord().iter()), vec![a]);
+        assert_eq!(sorted(store.changed_health().iter()), vec![b]);
+        assert!(store.added_door().is_empty());
+    }
+
+    #[test]
+    fn removals_cancel_additions() {
+        let (mut store, ids) = store(2);
+        let (a, b) = (ids[0], ids[1]);
+        store.commit(insert::door(a)).unwrap();
+        store.commit(remove::door(a)).unwrap();
+        assert!(store.added_door().is_empty());
+        assert!(store.removed_door().is_empty());
+
+        store.commit(insert::health(b, 1)).unwrap();
+        store.clear_changes();
+        store.commit(insert::health(b, 2)).unwrap();
+        store.commit(remove::health(b)).unwrap();
+        assert!(store.changed_health().is_empty());
+        assert_eq!(sorted(store.removed_health().iter()), vec![b]);
+
+        // removing a component which isn't present records nothing
+        store.commit(remove::coord(a)).unwrap();
+        assert!(store.removed_coord().is_empty());
+    }
+
+    #[test]
+    fn reinserting_a_removed_component_is_a_change() {
+        let (mut store, ids) = store(1);
+        let id = ids[0];
+        store.commit(insert::coord(id, Coord::new(0, 0))).unwrap();
+        store.clear_changes();
+        store.commit(remove::coord(id)).unwrap();
+        store.commit(insert::coord(id, Coord::new(1, 1))).unwrap();
+        assert!(store.added_coord().is_empty());
+        assert!(store.removed_coord().is_empty());
+        assert_eq!(sorted(store.changed_coord().iter()), vec![id]);
+    }
+
+    #[test]
+    fn draining_takes_the_recorded_ids() {
+        let (mut store, ids) = store(3);
+        for &id in ids.iter() {
+            store.commit(insert::door(id)).unwrap();
+            store.commit(insert::tile(id, 1)).unwrap();
+        }
+        store.commit(remove::door(ids[1])).unwrap();
+
+        assert_eq!(sorted(store.drain_added_door().iter()), vec![ids[0], ids[2]]);
+        assert!(store.added_door().is_empty());
+        assert!(store.drain_changed_door().is_empty());
+        assert!(store.drain_removed_door().is_empty());
+
+        store.commit(remove::door(ids[2])).unwrap();
+        assert_eq!(sorted(store.drain_removed_door().iter()), vec![ids[2]]);
+        assert!(store.removed_door().is_empty());
+    }
+}
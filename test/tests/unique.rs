@@ -0,0 +1,113 @@
+#[macro_use] extern crate entity_store_test;
+
+tests! {
+    #[test]
+    fn replace_moves_component_to_new_entity() {
+        let mut world = world();
+        let a = world.spawn();
+        let b = world.spawn();
+        world.commit(insert::leader(a)).unwrap();
+        assert_eq!(world.entity_store().get_leader_entity(), Some(a));
+
+        match world.entity_store().implied_removal(&insert::leader(b)) {
+            Some(EntityChange::Remove(id, ComponentType::Leader)) => assert_eq!(id, a),
+            other => panic!("unexpected implied removal: {:?}", other),
+        }
+        assert!(world.check_commit(&insert::leader(b)).is_ok());
+        world.commit(insert::leader(b)).unwrap();
+        assert!(!world.entity_store().contains_leader(a));
+        assert!(world.entity_store().contains_leader(b));
+        assert_eq!(world.entity_store().get_leader_entity(), Some(b));
+
+        // giving the component to its owner again changes nothing
+        assert!(world.entity_store().implied_removal(&insert::leader(b)).is_none());
+        world.commit(insert::leader(b)).unwrap();
+        assert_eq!(world.entity_store().get_leader_entity(), Some(b));
+
+        world.commit(remove::leader(b)).unwrap();
+        assert_eq!(world.entity_store().get_leader_entity(), None);
+    }
+
+    #[test]
+    fn error_leaves_store_unchanged() {
+        let mut world = world();
+        let a = world.spawn();
+        let b = world.spawn();
+        world.commit(insert::target(a, 1)).unwrap();
+
+        let violation = world.entity_store().check_unique(&insert::target(b, 2)).unwrap_err();
+        assert_eq!(violation.component_type, ComponentType::Target);
+        assert_eq!(violation.owner, a);
+        assert_eq!(violation.id, b);
+        match world.try_commit(insert::target(b, 2)) {
+            Err(CommitError::UniqueViolation(violation)) => assert_eq!(violation.owner, a),
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        assert_eq!(world.commit(insert::target(b, 2)), Err(violation));
+        assert_eq!(world.commit_with_hooks(insert::target(b, 2), &mut EntityHooks::new()), Err(violation));
+        let mut journal = ChangeJournal::new();
+        assert_eq!(journal.commit(&mut world, insert::target(b, 2)), Err(violation));
+        assert!(!journal.can_undo());
+
+        // the rest of a batch is still committed
+        let result = world.commit_batch(vec![insert::target(b, 3), insert::tile(b, 1)]);
+        assert_eq!(result, Err(UniqueViolation { component_type: ComponentType::Target, owner: a, id: b }));
+        assert_eq!(world.entity_store().get_tile(b), Some(&1));
+
+        let mut store = world.entity_store().clone();
+        assert!(store.commit(insert::target(b, 4)).is_err());
+        assert!(EntityBuilder::new().target(4).commit(b, &mut store).is_err());
+        assert!(EntityBuilder::new().target(4).spawn(&mut world).is_err());
+        for store in [world.entity_store(), &store].iter() {
+            assert_eq!(store.get_target(a), Some(&1));
+            assert_eq!(store.get_target(b), None);
+            assert_eq!(store.get_target_entity(), Some(a));
+        }
+
+        // the owner may replace its own value
+        assert!(world.try_commit(insert::target(a, 5)).is_ok());
+        assert_eq!(world.entity_store().get_target(a), Some(&5));
+
+        world.commit(remove::target(a)).unwrap();
+        assert!(world.try_commit(insert::target(b, 6)).is_ok());
+        assert_eq!(world.entity_store().get_target_entity(), Some(b));
+    }
+
+    #[test]
+    fn store_commits_update_table_and_spatial_hash() {
+        let mut world = world();
+        let a = EntityBuilder::new().coord(Coord::new(1, 1)).leader().target(1).spawn(&mut world).unwrap();
+        let b = EntityBuilder::new().coord(Coord::new(2, 2)).spawn(&mut world).unwrap();
+        let mut store = world.entity_store().clone();
+        let mut table = world.entity_component_table().clone();
+        let mut spatial_hash = world.spatial_hash().clone();
+
+        for change in [insert::leader(b), insert::target(b, 2)].iter().cloned() {
+            table.update(&change);
+            spatial_hash.update(&store, &change, 1);
+            let _ = store.commit(change);
+        }
+        assert!(!table.get(a).contains(ComponentType::Leader));
+        assert!(table.get(b).contains(ComponentType::Leader));
+        assert_eq!(spatial_hash.get(Coord::new(1, 1)).unwrap().leader_count, 0);
+        assert_eq!(spatial_hash.get(Coord::new(2, 2)).unwrap().leader_count, 1);
+
+        // the rejected change is ignored by both
+        assert!(table.get(a).contains(ComponentType::Target));
+        assert!(!table.get(b).contains(ComponentType::Target));
+    }
+
+    #[test]
+    fn despawning_owner_releases_component() {
+        let mut world = world();
+        let a = world.spawn();
+        let b = world.spawn();
+        world.commit(insert::target(a, 1)).unwrap();
+        world.commit(insert::leader(a)).unwrap();
+        world.despawn(a);
+        assert_eq!(world.entity_store().get_target_entity(), None);
+        assert_eq!(world.entity_store().get_leader_entity(), None);
+        assert!(world.try_commit(insert::target(b, 2)).is_ok());
+    }
+}